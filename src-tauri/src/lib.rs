@@ -1,7 +1,11 @@
 use base64::{engine::general_purpose, Engine as _};
+use chrono::{Datelike, Timelike};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, LazyLock, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -11,14 +15,144 @@ use tauri::{Emitter, Manager};
 use tauri_plugin_aptabase::EventTracker;
 use tauri_plugin_autostart::ManagerExt;
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
-use tauri_plugin_oauth::start;
+use tauri_plugin_oauth::{cancel, start};
 
 // Type alias for the app handle to avoid generic complexity
 type AppHandle = tauri::AppHandle<tauri::Wry>;
 
+// Error returned by tray commands when the tray icon doesn't exist
+const TRAY_NOT_AVAILABLE: &str = "tray not available";
+
+// Number of days kept in history.json
+const HISTORY_RETENTION_DAYS: usize = 30;
+
+// Bumped on every attention change so a pending auto-clear can tell it is stale
+static TRAY_ATTENTION_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Bumped to schedule or cancel the auto-start countdown; a countdown stops as
+// soon as it sees a newer generation
+static AUTO_START_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Whether the timer wants the focus tick sound, and the looping player process
+// playing it (None while ticking is off in settings or muted)
+static TICK_WANTED: AtomicBool = AtomicBool::new(false);
+static TICK_PLAYER: Mutex<Option<std::process::Child>> = Mutex::new(None);
+
+// Port of the running OAuth callback server, and a counter bumped whenever it
+// is stopped so callbacks from an older server are dropped
+static OAUTH_PORT: Mutex<Option<u16>> = Mutex::new(None);
+static OAUTH_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Disambiguates ids generated within the same millisecond
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// Menu-bar-only mode: no dock (macOS) or taskbar entry, even while the window
+// is shown
+static MENUBAR_ONLY: AtomicBool = AtomicBool::new(false);
+
+// Whether the main window is currently shrunk to the mini timer
+static MINI_MODE: AtomicBool = AtomicBool::new(false);
+const MINI_WINDOW_SIZE: WindowSize = WindowSize {
+    width: 240.0,
+    height: 110.0,
+};
+// Matches the window size in tauri.conf.json
+const DEFAULT_WINDOW_SIZE: WindowSize = WindowSize {
+    width: 800.0,
+    height: 600.0,
+};
+
+// Set once the app data directory turned out to be unavailable and a temp
+// directory is used instead
+static DATA_DIR_FALLBACK: AtomicBool = AtomicBool::new(false);
+
+// Read-only/guest mode: when set, every save/delete command refuses to touch disk
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+// Active workspace, None = the root data directory (the pre-workspace layout)
+static ACTIVE_WORKSPACE: Mutex<Option<String>> = Mutex::new(None);
+
+// User-chosen data directory replacing the app data directory, None = default
+static DATA_DIR_OVERRIDE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// Every JSON store kept in a data directory (yearly history archives aside)
+const STORE_FILES: &[&str] = &[
+    "settings.json",
+    "session.json",
+    "tasks.json",
+    "history.json",
+    "manual_sessions.json",
+    "tags.json",
+    "session_tags.json",
+    "timer_state.json",
+];
+
 // Global activity monitoring state
 static ACTIVITY_MONITOR: Mutex<Option<ActivityMonitor>> = Mutex::new(None);
 
+// Global file logger, installed during setup once the app data directory is known
+static LOGGER: FileLogger = FileLogger {
+    sink: Mutex::new(None),
+};
+
+// Rotate presto.log once it grows past this size, keeping a few old files around
+const MAX_LOG_FILE_SIZE: u64 = 1024 * 1024; // 1 MB
+const MAX_LOG_FILES: u32 = 5;
+
+// Automatic daily backups kept in backups/ before the oldest is removed
+const MAX_AUTO_BACKUPS: usize = 7;
+
+// App handle used by the macOS screen lock observer callbacks
+#[cfg(target_os = "macos")]
+static SCREEN_LOCK_APP_HANDLE: std::sync::OnceLock<AppHandle> = std::sync::OnceLock::new();
+
+// Settings needed by OS callbacks and run events, which can't load settings
+// themselves, and by the once-a-second tray update; kept in sync by
+// cache_runtime_settings
+static LOCK_PAUSES_TIMER: AtomicBool = AtomicBool::new(false);
+static DEBUG_MODE: AtomicBool = AtomicBool::new(false);
+static TRAY_DISPLAY: Mutex<Option<(String, ModeIcons)>> = Mutex::new(None); // title format, icons
+
+// Serializes the operations that read or rewrite several stores at once
+// (workspace switch, data dir migration, session bundles, retagging, clearing
+// completed tasks, auto-export) against each other and against the saves of
+// manual sessions, tags and session tags. The lock isn't reentrant, so code
+// holding it writes those stores through the write_* helpers instead of the
+// save_* commands.
+static DATA_LOCK: LazyLock<tauri::async_runtime::Mutex<()>> =
+    LazyLock::new(|| tauri::async_runtime::Mutex::new(()));
+
+// Latest task list waiting for a debounced write, and a counter so only the
+// most recent scheduled write actually fires
+static PENDING_TASKS: Mutex<Option<Vec<Task>>> = Mutex::new(None);
+static PENDING_TASKS_GENERATION: AtomicU64 = AtomicU64::new(0);
+const TASK_SAVE_DEBOUNCE: Duration = Duration::from_millis(750);
+
+// Cached tag totals keyed by (day, days, sorted types). An entry is only valid
+// for the generation it was computed in; every write to a store the totals
+// read from bumps the generation.
+type TagTotalsKey = (chrono::NaiveDate, u32, Vec<String>);
+static STATS_GENERATION: AtomicU64 = AtomicU64::new(0);
+static TAG_TOTALS_CACHE: LazyLock<Mutex<HashMap<TagTotalsKey, (u64, Vec<TagTotal>)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+// Cached range summaries keyed by (day, range days), same generation rules
+type StatsSummaryKey = (chrono::NaiveDate, u32);
+static STATS_SUMMARY_CACHE: LazyLock<Mutex<HashMap<StatsSummaryKey, (u64, RangeSummary)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// When the last automatic export ran, None = not yet in this run
+static LAST_AUTO_EXPORT: Mutex<Option<Instant>> = Mutex::new(None);
+const AUTO_EXPORT_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+const BATTERY_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+// Session progress (current, total) for the tray, kept here so update_tray_icon
+// doesn't need it on every tick
+static SESSION_PROGRESS: Mutex<(u32, u32)> = Mutex::new((1, 0));
+
+// Custom image currently shown as the tray icon, so it isn't reloaded on every tick
+static TRAY_MODE_IMAGE: Mutex<Option<String>> = Mutex::new(None);
+
 // Global shortcut debounce state
 static SHORTCUT_DEBOUNCE: LazyLock<Mutex<HashMap<String, Instant>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
@@ -36,6 +170,37 @@ struct PomodoroSession {
     total_focus_time: u32, // in seconds
     current_session: u32,
     date: String,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    tag_ids: Option<Vec<String>>,
+    #[serde(default)]
+    total_focus_time_ms: Option<u64>, // precise focus time, None for data saved before it existed
+}
+
+impl PomodoroSession {
+    // Prefer the millisecond total so repeated rounding doesn't drift daily totals
+    fn focus_time_ms(&self) -> u64 {
+        self.total_focus_time_ms
+            .unwrap_or(self.total_focus_time as u64 * 1000)
+    }
+}
+
+// A running timer as last saved by the frontend, so a crash or force quit
+// doesn't silently lose it
+#[derive(Serialize, Deserialize, Clone)]
+struct TimerState {
+    mode: String,        // "focus", "break" or "longBreak"
+    started_at: String,  // ISO string of when the timer was (re)started
+    remaining_secs: u32, // left on the timer at started_at
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct LoadedTimerState {
+    #[serde(flatten)]
+    state: TimerState,
+    stale: bool,       // the timer would have finished while the app was closed
+    elapsed_secs: u64, // time since started_at
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -58,6 +223,14 @@ struct Tag {
     icon: String,  // emoji or remix icon class
     color: String, // hex color code
     created_at: String,
+    #[serde(default)]
+    pinned: Option<bool>, // missing in tag files written before pinning existed
+}
+
+impl Tag {
+    fn is_pinned(&self) -> bool {
+        self.pinned.unwrap_or(false)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -68,6 +241,229 @@ struct SessionTag {
     created_at: String,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+struct BatteryStatus {
+    percent: u8,
+    charging: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct StreakInfo {
+    current_streak: u32, // consecutive days up to today (or yesterday)
+    longest_streak: u32,
+    last_active_date: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct PlanEstimate {
+    finish_time: String, // ISO 8601 local datetime
+    total_focus_minutes: u32,
+    total_break_minutes: u32,
+    long_breaks: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct WeekdayAvg {
+    weekday: String,
+    avg_focus_minutes: f64,
+    avg_pomodoros: f64,
+    days_with_data: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct DayStat {
+    pomodoros: u32, // the larger of the timer history and the manual focus sessions
+    focus_minutes: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct DayValue {
+    date: String,
+    value: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct FocusBreakRatio {
+    total_focus_minutes: u32,
+    total_break_minutes: u32,
+    ratio: Option<f64>, // focus / break, None when no break time was recorded
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct TagTotal {
+    tag_id: String,
+    name: String,
+    icon: String,
+    color: String,
+    total_duration: u64, // in seconds
+    session_count: u32,
+    last_used: Option<String>, // ISO string of the most recent session
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RecentSession {
+    id: String,
+    date: String,
+    #[serde(rename = "type")]
+    session_type: String,
+    duration: u32, // in minutes
+    tags: Vec<serde_json::Value>,
+    notes: Option<String>,
+    created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RangeSummary {
+    focus_minutes: u32,
+    sessions: u32, // focus sessions, timer ones included since they are saved as manual sessions
+    active_days: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Dashboard {
+    summary: RangeSummary,
+    tag_totals: Vec<TagTotal>,
+    goal_pace: GoalPace,
+    streak: StreakInfo,
+    recent_sessions: Vec<RecentSession>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct TagPair {
+    tag_a: String, // tag ids, tag_a < tag_b
+    tag_b: String,
+    count: u32, // manual sessions carrying both tags
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct IdleDebug {
+    idle_seconds: f64,
+    raw_value: Option<u64>, // HIDIdleTime in nanoseconds
+    source: String,         // "iokit", "ioreg" or "unavailable"
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SessionBundle {
+    session: PomodoroSession,
+    manual_session: Option<ManualSession>,
+    #[serde(default)]
+    session_tags: Vec<SessionTag>, // appended to session_tags.json
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SkippedRow {
+    line: usize,
+    reason: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct ImportSummary {
+    imported: u32,
+    created_tags: u32,
+    skipped: Vec<SkippedRow>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct TypeSlice {
+    #[serde(rename = "type")]
+    session_type: String,
+    minutes: u32,
+    percent: u32, // slices always add up to exactly 100
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct AvgGap {
+    avg_gap_minutes: f64, // mean of the per-day averages
+    days_counted: u32,    // days with at least one gap
+    gaps_counted: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct FocusScore {
+    score: u32,    // 0-100
+    grade: String, // "A" to "F"
+    completed_pomodoros: u32,
+    focus_minutes: u32,
+    break_adherence: f64, // 0-1
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct DailyGoal {
+    goal_minutes: u32,
+    completed_minutes: u32, // today's focus minutes
+    percent: f64,           // may exceed 100
+    met: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct GoalPace {
+    weekly_goal_minutes: u32,
+    minutes_done: u32,
+    minutes_remaining: u32,
+    remaining_days: u32, // including today
+    minutes_per_remaining_day: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Trend {
+    current: u32,  // focus minutes in the last 7 days
+    previous: u32, // focus minutes in the 7 days before that
+    delta: i64,
+    percent_change: Option<f64>, // None when the previous period is empty
+    direction: String,           // "up", "down" or "flat"
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct WeeklySessionLength {
+    week_start: String,
+    average_minutes: f64,
+    session_count: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct AvgSessionLength {
+    average_minutes: f64,
+    session_count: u32,
+    weekly: Vec<WeeklySessionLength>, // oldest week first, for trend lines
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct WeekInfo {
+    iso_week: u32,
+    year: i32,          // ISO week-based year, can differ from the calendar year
+    start_date: String, // Monday, or Sunday with week_start_day "sunday"
+    end_date: String,   // six days after start_date
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct FileHealth {
+    file: String,
+    status: String, // "ok", "missing", "corrupt" or "migrated"
+    detail: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct HealthReport {
+    healthy: bool, // false if any store is corrupt
+    files: Vec<FileHealth>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct BackupInfo {
+    name: String,
+    size_bytes: u64,
+    created_at: Option<String>, // ISO string, None if the filesystem doesn't report it
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct TaskStats {
+    total_tasks: u32,
+    completed_tasks: u32,
+    completion_rate: f64,                 // 0.0 - 1.0
+    avg_minutes_to_complete: Option<f64>, // None when no task has both timestamps
+    created_today: u32,
+}
+
 #[derive(Serialize, Deserialize)]
 struct Task {
     id: u64,
@@ -91,6 +487,54 @@ struct AppSettings {
     hide_icon_on_close: bool,
     #[serde(default)]
     hide_status_bar: bool,
+    #[serde(default = "default_close_behavior")]
+    close_behavior: String, // "minimize" or "quit"
+    #[serde(default = "default_tray_title_format")]
+    tray_title_format: String, // placeholders: {icon} {time} {mode} {session} {total}
+    #[serde(default)]
+    preferred_monitor: Option<String>, // monitor name, None = leave the window where it is
+    #[serde(default)]
+    break_always_on_top: bool, // keep the window in front during breaks
+    #[serde(default)]
+    mode_icons: ModeIcons,
+    #[serde(default)]
+    mini_mode_frameless: bool, // hide the title bar in mini mode
+    #[serde(default)]
+    mini_mode_always_on_top: bool,
+    #[serde(default)]
+    normal_window_size: Option<WindowSize>, // size to restore when leaving mini mode
+    #[serde(default = "default_theme")]
+    theme: String, // "system", "light" or "dark"
+    #[serde(default)]
+    start_hidden_from_dock: bool, // launch as a menu-bar-only app (no dock/taskbar icon)
+}
+
+// Window size in logical pixels
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct WindowSize {
+    width: f64,
+    height: f64,
+}
+
+// User-chosen tray icons: an emoji/text glyph or the path of a PNG/ICO image.
+// None = the built-in glyph for that mode.
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct ModeIcons {
+    focus: Option<String>,
+    #[serde(rename = "break")]
+    break_: Option<String>,
+    long_break: Option<String>,
+}
+
+impl ModeIcons {
+    fn for_mode(&self, mode: &str) -> Option<&str> {
+        match mode {
+            "focus" => self.focus.as_deref(),
+            "break" => self.break_.as_deref(),
+            "longBreak" => self.long_break.as_deref(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -98,6 +542,8 @@ struct ShortcutSettings {
     start_stop: Option<String>,
     reset: Option<String>,
     skip: Option<String>,
+    #[serde(default)]
+    extend: Option<String>, // adds 5 minutes to the running session
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -108,20 +554,127 @@ struct TimerSettings {
     total_sessions: u32,
     #[serde(default = "default_weekly_goal")]
     weekly_goal_minutes: u32,
+    #[serde(default = "default_daily_reset_enabled")]
+    daily_reset_enabled: bool, // zero the counters when the saved session isn't from today
+    #[serde(default = "default_long_break_interval")]
+    long_break_interval: u32, // a long break follows every Nth completed pomodoro
+    #[serde(default)]
+    daily_goal_minutes: u32, // 0 = derived from the weekly goal
+    #[serde(default)]
+    type_durations: BTreeMap<String, u32>, // minutes for custom session types
+    #[serde(default = "default_week_start_day")]
+    week_start_day: String, // "monday" (ISO) or "sunday"
+}
+
+impl TimerSettings {
+    // Never divide by zero, even with a hand-edited settings file
+    fn long_break_interval(&self) -> u32 {
+        self.long_break_interval.max(1)
+    }
+
+    // Built-in types use their own durations; anything else is looked up in
+    // type_durations and falls back to a focus session's length
+    fn default_duration(&self, session_type: &str) -> u32 {
+        match session_type {
+            "focus" => self.focus_duration,
+            "break" => self.break_duration,
+            "longBreak" => self.long_break_duration,
+            other => self
+                .type_durations
+                .get(other)
+                .copied()
+                .filter(|minutes| *minutes > 0)
+                .unwrap_or(self.focus_duration),
+        }
+    }
+
+    fn week_start(&self) -> chrono::Weekday {
+        if self.week_start_day == "sunday" {
+            chrono::Weekday::Sun
+        } else {
+            chrono::Weekday::Mon
+        }
+    }
+
+    // An unset daily goal spreads the weekly goal over a five-day week
+    fn daily_goal_minutes(&self) -> u32 {
+        if self.daily_goal_minutes > 0 {
+            self.daily_goal_minutes
+        } else {
+            self.weekly_goal_minutes / 5
+        }
+    }
 }
 
 fn default_weekly_goal() -> u32 {
     125
 }
 
+fn default_week_start_day() -> String {
+    "monday".to_string()
+}
+
+fn default_long_break_interval() -> u32 {
+    4 // mirrors the frontend timer
+}
+
+fn default_max_manual_session_minutes() -> u32 {
+    480 // 8 hours
+}
+
+fn default_auto_export_interval_hours() -> u32 {
+    24
+}
+
+fn default_durable_writes() -> bool {
+    true
+}
+
+fn default_daily_reset_enabled() -> bool {
+    true
+}
+
 fn default_analytics_enabled() -> bool {
     true // Analytics enabled by default
 }
 
-// Helper function to check if analytics are enabled
+fn default_close_behavior() -> String {
+    "minimize".to_string()
+}
+
+fn default_theme() -> String {
+    "system".to_string()
+}
+
+fn default_tick_interval_secs() -> u32 {
+    1
+}
+
+fn default_tick_volume() -> f32 {
+    0.3
+}
+
+fn default_low_battery_threshold() -> u32 {
+    20
+}
+
+fn default_quiet_start() -> String {
+    "22:00".to_string()
+}
+
+fn default_quiet_end() -> String {
+    "07:00".to_string()
+}
+
+fn default_tray_title_format() -> String {
+    "{icon} {time}".to_string()
+}
+
+// Helper function to check if analytics are enabled.
+// Debug mode (3-second timers) never reports anything, regardless of analytics_enabled.
 async fn are_analytics_enabled(app: &AppHandle) -> bool {
     match load_settings(app.clone()).await {
-        Ok(settings) => settings.analytics_enabled,
+        Ok(settings) => settings.analytics_enabled && !settings.advanced.debug_mode,
         Err(_) => true, // Default to enabled if we can't load settings
     }
 }
@@ -134,20 +687,73 @@ struct NotificationSettings {
     #[serde(default)]
     auto_start_focus: bool,
     #[serde(default)]
+    auto_start_delay_secs: u32, // countdown before an auto-started focus session, 0 = immediate
+    #[serde(default)]
     allow_continuous_sessions: bool,
     smart_pause: bool,
     smart_pause_timeout: u32, // timeout in seconds
+    #[serde(default)]
+    lock_pauses_timer: bool, // pause when the screen is locked (macOS)
+    #[serde(default)]
+    break_smart_pause_timeout: Option<u32>, // None = never pause during breaks
+    #[serde(default)]
+    long_break_smart_pause_timeout: Option<u32>, // None = never pause during long breaks
+    #[serde(default)]
+    quiet_hours_enabled: bool,
+    #[serde(default = "default_quiet_start")]
+    quiet_start: String, // "HH:MM"
+    #[serde(default = "default_quiet_end")]
+    quiet_end: String, // "HH:MM"
+    #[serde(default)]
+    muted_until: Option<String>, // ISO timestamp, one-off do-not-disturb deadline
+    #[serde(default)]
+    sound_focus_end: Option<String>, // custom sound file paths, None = system default
+    #[serde(default)]
+    sound_break_end: Option<String>,
+    #[serde(default)]
+    sound_long_break_end: Option<String>,
+    #[serde(default)]
+    tick_sound_enabled: bool, // ticking clock during focus sessions
+    #[serde(default = "default_tick_interval_secs")]
+    tick_interval_secs: u32,
+    #[serde(default = "default_tick_volume")]
+    tick_volume: f32, // 0.0 - 1.0, alerts always play at full volume
+    #[serde(default = "default_low_battery_threshold")]
+    low_battery_threshold: u32, // percent, 0 = never warn
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 struct AdvancedSettings {
     #[serde(default)]
     debug_mode: bool, // Debug mode with 3-second timers
+    #[serde(default = "default_max_manual_session_minutes")]
+    max_manual_session_minutes: u32, // longer manual sessions need an explicit override
+    #[serde(default = "default_durable_writes")]
+    durable_writes: bool, // fsync critical stores before replacing them; off trades safety for speed
+    #[serde(default)]
+    auto_export_enabled: bool,
+    #[serde(default)]
+    auto_export_path: String, // directory the export is written to
+    #[serde(default = "default_auto_export_interval_hours")]
+    auto_export_interval_hours: u32,
+    #[serde(default)]
+    auto_export_timestamped: bool, // keep every export instead of overwriting the last one
+    #[serde(default)]
+    completion_webhook_url: Option<String>, // POSTed to whenever a session completes
 }
 
 impl Default for AdvancedSettings {
     fn default() -> Self {
-        Self { debug_mode: false }
+        Self {
+            debug_mode: false,
+            max_manual_session_minutes: default_max_manual_session_minutes(),
+            durable_writes: default_durable_writes(),
+            auto_export_enabled: false,
+            auto_export_path: String::new(),
+            auto_export_interval_hours: default_auto_export_interval_hours(),
+            auto_export_timestamped: false,
+            completion_webhook_url: None,
+        }
     }
 }
 
@@ -158,6 +764,7 @@ impl Default for AppSettings {
                 start_stop: Some("CommandOrControl+Alt+Space".to_string()),
                 reset: Some("CommandOrControl+Alt+R".to_string()),
                 skip: Some("CommandOrControl+Alt+S".to_string()),
+                extend: Some("CommandOrControl+Alt+E".to_string()),
             },
             timer: TimerSettings {
                 focus_duration: 25,
@@ -165,21 +772,51 @@ impl Default for AppSettings {
                 long_break_duration: 20,
                 total_sessions: 10,
                 weekly_goal_minutes: 125,
+                daily_reset_enabled: true,
+                long_break_interval: default_long_break_interval(),
+                daily_goal_minutes: 0, // derived from the weekly goal
+                type_durations: BTreeMap::new(),
+                week_start_day: default_week_start_day(),
             },
             notifications: NotificationSettings {
                 desktop_notifications: true,
                 sound_notifications: true,
                 auto_start_timer: true,
                 auto_start_focus: false,          // default to disabled
+                auto_start_delay_secs: 0,         // start right away
                 allow_continuous_sessions: false, // default to disabled
                 smart_pause: false,
-                smart_pause_timeout: 30, // default 30 seconds
+                smart_pause_timeout: 30,  // default 30 seconds
+                lock_pauses_timer: false, // default to disabled
+                break_smart_pause_timeout: None,
+                long_break_smart_pause_timeout: None,
+                quiet_hours_enabled: false, // default to disabled
+                quiet_start: default_quiet_start(),
+                quiet_end: default_quiet_end(),
+                muted_until: None,
+                sound_focus_end: None,
+                sound_break_end: None,
+                sound_long_break_end: None,
+                tick_sound_enabled: false, // default to disabled
+                tick_interval_secs: default_tick_interval_secs(),
+                tick_volume: default_tick_volume(),
+                low_battery_threshold: default_low_battery_threshold(),
             },
             advanced: AdvancedSettings::default(),
             autostart: false,          // default to disabled
             analytics_enabled: true,   // default to enabled
             hide_icon_on_close: false, // default to disabled
             hide_status_bar: false,    // default to disabled
+            close_behavior: default_close_behavior(),
+            tray_title_format: default_tray_title_format(),
+            preferred_monitor: None,
+            break_always_on_top: false, // default to disabled
+            mode_icons: ModeIcons::default(),
+            mini_mode_frameless: false,
+            mini_mode_always_on_top: false,
+            normal_window_size: None,
+            theme: default_theme(),
+            start_hidden_from_dock: false, // default to disabled
         }
     }
 }
@@ -200,54 +837,268 @@ fn should_debounce_shortcut(action: &str) -> bool {
     false // Should not debounce
 }
 
-impl ActivityMonitor {
-    fn new(app_handle: AppHandle, timeout_seconds: u64) -> Self {
-        Self {
-            last_activity: Arc::new(Mutex::new(Instant::now())),
-            is_monitoring: Arc::new(Mutex::new(false)),
-            app_handle,
-            inactivity_threshold: Arc::new(Mutex::new(Duration::from_secs(timeout_seconds))),
+struct LogSink {
+    path: PathBuf,
+    file: fs::File,
+}
+
+struct FileLogger {
+    sink: Mutex<Option<LogSink>>,
+}
+
+impl FileLogger {
+    // Shift presto.log -> presto.log.1 -> ... -> presto.log.N, dropping the oldest
+    fn rotate(sink: &mut LogSink) -> std::io::Result<()> {
+        let rotated = |index: u32| PathBuf::from(format!("{}.{}", sink.path.display(), index));
+
+        let _ = fs::remove_file(rotated(MAX_LOG_FILES - 1));
+        for index in (1..MAX_LOG_FILES - 1).rev() {
+            let _ = fs::rename(rotated(index), rotated(index + 1));
         }
+        fs::rename(&sink.path, rotated(1))?;
+
+        sink.file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&sink.path)?;
+        Ok(())
     }
+}
 
-    #[cfg(target_os = "macos")]
-    fn start_monitoring(&self) -> Result<(), String> {
-        let mut is_monitoring = self.is_monitoring.lock().unwrap();
-        if *is_monitoring {
-            return Ok(()); // Already monitoring
-        }
-        *is_monitoring = true;
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
 
-        let last_activity = Arc::clone(&self.last_activity);
-        let is_monitoring_clone = Arc::clone(&self.is_monitoring);
-        let inactivity_threshold = Arc::clone(&self.inactivity_threshold);
-        let app_handle = self.app_handle.clone();
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
 
-        thread::spawn(move || {
-            loop {
-                // Check if we should stop monitoring
-                {
-                    let monitoring = is_monitoring_clone.lock().unwrap();
-                    if !*monitoring {
-                        break;
-                    }
-                }
+        let line = format!(
+            "{} {:<5} [{}] {}",
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
 
-                // Get current threshold
-                let threshold = {
-                    let threshold_guard = inactivity_threshold.lock().unwrap();
-                    *threshold_guard
-                };
+        // Keep console output during development
+        if cfg!(debug_assertions) {
+            eprintln!("{}", line);
+        }
 
-                // Check system activity
-                let has_activity = Self::check_system_activity();
+        let mut sink_guard = self.sink.lock().unwrap();
+        if let Some(sink) = sink_guard.as_mut() {
+            let _ = writeln!(sink.file, "{}", line);
 
-                if has_activity {
-                    // Update last activity time
-                    {
-                        let mut last = last_activity.lock().unwrap();
-                        *last = Instant::now();
-                    }
+            let size = sink.file.metadata().map(|m| m.len()).unwrap_or(0);
+            if size > MAX_LOG_FILE_SIZE {
+                let _ = Self::rotate(sink);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(sink) = self.sink.lock().unwrap().as_mut() {
+            let _ = sink.file.flush();
+        }
+    }
+}
+
+// The platform app data directory. Logs and the workspace/data dir pointer
+// files always live here, whatever data directory is in use.
+// Falls back to a temp directory when the system can't provide an app data
+// directory, so the app keeps running (without lasting data) instead of every
+// command failing. The first fallback is logged and announced with a
+// "data-dir-fallback" event.
+fn get_app_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    match app.path().app_data_dir() {
+        Ok(dir) => Ok(dir),
+        Err(e) => {
+            let fallback = std::env::temp_dir().join("presto-data");
+            if !DATA_DIR_FALLBACK.swap(true, Ordering::SeqCst) {
+                log::error!(
+                    "Failed to get app data directory ({}), using {} for this run",
+                    e,
+                    fallback.display()
+                );
+                let _ = app.emit("data-dir-fallback", fallback.to_string_lossy().to_string());
+            }
+            Ok(fallback)
+        }
+    }
+}
+
+// Lets the frontend show the warning even if it missed the event during startup
+#[tauri::command]
+fn is_data_dir_fallback() -> bool {
+    DATA_DIR_FALLBACK.load(Ordering::SeqCst)
+}
+
+fn get_root_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    match DATA_DIR_OVERRIDE.lock().unwrap().clone() {
+        Some(dir) => Ok(dir),
+        None => get_app_data_dir(app),
+    }
+}
+
+// Directory every store reads from and writes to: workspaces/<name>/ when a
+// workspace is active, the app data directory itself otherwise
+fn get_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let root = get_root_data_dir(app)?;
+    Ok(match ACTIVE_WORKSPACE.lock().unwrap().as_deref() {
+        Some(name) => root.join("workspaces").join(name),
+        None => root,
+    })
+}
+
+// Workspace names become directory names, so keep them to a safe character set
+fn validate_workspace_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid workspace name '{}' (use letters, digits, '-' or '_')",
+            name
+        ))
+    }
+}
+
+// Restore the data directory chosen with migrate_data_dir
+fn load_data_dir_override(app: &AppHandle) -> Result<(), String> {
+    let path = get_app_data_dir(app)?.join("data_dir.json");
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read data dir file: {}", e))?;
+    let data_dir: Option<PathBuf> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse data dir file: {}", e))?;
+
+    *DATA_DIR_OVERRIDE.lock().unwrap() = data_dir;
+    Ok(())
+}
+
+// Restore the workspace that was active when the app last ran
+fn load_active_workspace(app: &AppHandle) -> Result<(), String> {
+    let path = get_app_data_dir(app)?.join("workspace.json");
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read workspace file: {}", e))?;
+    let workspace: Option<String> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse workspace file: {}", e))?;
+    if let Some(name) = &workspace {
+        validate_workspace_name(name)?;
+    }
+
+    *ACTIVE_WORKSPACE.lock().unwrap() = workspace;
+    Ok(())
+}
+
+// Logs are shared by all workspaces
+fn get_log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(get_app_data_dir(app)?.join("logs"))
+}
+
+fn init_logging(app: &AppHandle) -> Result<(), String> {
+    let log_dir = get_log_dir(app)?;
+    fs::create_dir_all(&log_dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+
+    let path = log_dir.join("presto.log");
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open log file: {}", e))?;
+
+    *LOGGER.sink.lock().unwrap() = Some(LogSink { path, file });
+
+    log::set_logger(&LOGGER).map_err(|e| format!("Failed to install logger: {}", e))?;
+    log::set_max_level(if cfg!(debug_assertions) {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_log_path(app: AppHandle) -> Result<String, String> {
+    Ok(get_log_dir(&app)?
+        .join("presto.log")
+        .to_string_lossy()
+        .to_string())
+}
+
+#[tauri::command]
+async fn set_log_level(level: String) -> Result<(), String> {
+    let filter: log::LevelFilter = level
+        .parse()
+        .map_err(|_| format!("Invalid log level '{}'", level))?;
+    log::set_max_level(filter);
+    log::info!("Log level set to {}", filter);
+    Ok(())
+}
+
+impl ActivityMonitor {
+    fn new(app_handle: AppHandle, timeout_seconds: u64) -> Self {
+        Self {
+            last_activity: Arc::new(Mutex::new(Instant::now())),
+            is_monitoring: Arc::new(Mutex::new(false)),
+            app_handle,
+            inactivity_threshold: Arc::new(Mutex::new(Duration::from_secs(timeout_seconds))),
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    fn start_monitoring(&self) -> Result<(), String> {
+        let mut is_monitoring = self.is_monitoring.lock().unwrap();
+        if *is_monitoring {
+            return Ok(()); // Already monitoring
+        }
+        *is_monitoring = true;
+
+        let last_activity = Arc::clone(&self.last_activity);
+        let is_monitoring_clone = Arc::clone(&self.is_monitoring);
+        let inactivity_threshold = Arc::clone(&self.inactivity_threshold);
+        let app_handle = self.app_handle.clone();
+
+        thread::spawn(move || {
+            loop {
+                // Check if we should stop monitoring
+                {
+                    let monitoring = is_monitoring_clone.lock().unwrap();
+                    if !*monitoring {
+                        break;
+                    }
+                }
+
+                // Get current threshold
+                let threshold = {
+                    let threshold_guard = inactivity_threshold.lock().unwrap();
+                    *threshold_guard
+                };
+
+                // Check system activity
+                let has_activity = Self::check_system_activity();
+
+                if has_activity {
+                    // Update last activity time
+                    {
+                        let mut last = last_activity.lock().unwrap();
+                        *last = Instant::now();
+                    }
 
                     // Emit activity event to frontend
                     let _ = app_handle.emit("user-activity", ());
@@ -285,6 +1136,15 @@ impl ActivityMonitor {
 
     #[cfg(target_os = "macos")]
     fn get_system_idle_time() -> f64 {
+        // If ioreg fails, assume no idle time (active)
+        Self::read_hid_idle_time_ns()
+            .map(|idle_ns| idle_ns as f64 / 1_000_000_000.0)
+            .unwrap_or(0.0)
+    }
+
+    // Raw HIDIdleTime in nanoseconds, or None if it couldn't be read
+    #[cfg(target_os = "macos")]
+    fn read_hid_idle_time_ns() -> Option<u64> {
         use std::process::Command;
 
         // Use ioreg to get HID idle time - most reliable method on macOS
@@ -305,16 +1165,14 @@ impl ActivityMonitor {
                             .trim_end_matches(|c: char| !c.is_ascii_digit());
 
                         if let Ok(idle_ns) = cleaned.parse::<u64>() {
-                            // Convert nanoseconds to seconds
-                            return idle_ns as f64 / 1_000_000_000.0;
+                            return Some(idle_ns);
                         }
                     }
                 }
             }
         }
 
-        // If ioreg fails, assume no idle time (active)
-        0.0
+        None
     }
 
     fn stop_monitoring(&self) {
@@ -374,18 +1232,378 @@ async fn update_activity_timeout(timeout_seconds: u64) -> Result<(), String> {
     }
 }
 
+// Report where idle time comes from, so a failing ioreg parse (which silently
+// falls back to 0.0) can be told apart from a real reading
+#[tauri::command]
+async fn debug_idle_source() -> Result<IdleDebug, String> {
+    #[cfg(target_os = "macos")]
+    {
+        let raw_value = ActivityMonitor::read_hid_idle_time_ns();
+        return Ok(IdleDebug {
+            idle_seconds: raw_value
+                .map(|idle_ns| idle_ns as f64 / 1_000_000_000.0)
+                .unwrap_or(0.0),
+            raw_value,
+            source: if raw_value.is_some() {
+                "ioreg"
+            } else {
+                "unavailable"
+            }
+            .to_string(),
+        });
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        Ok(IdleDebug {
+            idle_seconds: 0.0,
+            raw_value: None,
+            source: "unavailable".to_string(),
+        })
+    }
+}
+
+// Apply the inactivity threshold that fits the current session mode, so going
+// idle during a break doesn't trigger smart pause
+#[tauri::command]
+async fn set_threshold_for_mode(mode: String, app: AppHandle) -> Result<(), String> {
+    let settings = load_settings(app).await?;
+    let notifications = &settings.notifications;
+
+    let timeout_seconds = match mode.as_str() {
+        "focus" => Some(notifications.smart_pause_timeout),
+        "break" => notifications.break_smart_pause_timeout,
+        "longBreak" => notifications.long_break_smart_pause_timeout,
+        other => return Err(format!("Unknown session mode '{}'", other)),
+    };
+
+    let monitor = ACTIVITY_MONITOR.lock().unwrap();
+    if let Some(ref monitor) = *monitor {
+        // No threshold configured for this mode means it should never fire
+        monitor.update_threshold(timeout_seconds.map(u64::from).unwrap_or(u64::MAX));
+        Ok(())
+    } else {
+        Err("Activity monitor not initialized".to_string())
+    }
+}
+
+fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::SeqCst)
+}
+
+// Guard for the top of every command that writes to the data directory
+fn ensure_writable() -> Result<(), String> {
+    if is_read_only() {
+        Err("read-only mode".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// Replace a file through a temp file and rename, so a crash mid-write never
+// leaves it truncated. When durable, the data (and on Unix the directory entry)
+// is flushed to disk before returning, so it also survives a power loss.
+fn write_file_atomic(
+    path: &std::path::Path,
+    contents: impl AsRef<[u8]>,
+    durable: bool,
+) -> std::io::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(contents.as_ref())?;
+    if durable {
+        file.sync_all()?;
+    }
+    drop(file);
+
+    fs::rename(&temp_path, path)?;
+
+    #[cfg(unix)]
+    if durable {
+        if let Some(dir) = path.parent() {
+            fs::File::open(dir)?.sync_all()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_read_only(enabled: bool) -> Result<(), String> {
+    READ_ONLY.store(enabled, Ordering::SeqCst);
+    log::info!(
+        "Read-only mode {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_read_only() -> Result<bool, String> {
+    Ok(is_read_only())
+}
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+#[tauri::command]
+async fn get_workspace() -> Result<Option<String>, String> {
+    Ok(ACTIVE_WORKSPACE.lock().unwrap().clone())
+}
+
+#[tauri::command]
+async fn list_workspaces(app: AppHandle) -> Result<Vec<String>, String> {
+    let workspaces_dir = get_root_data_dir(&app)?.join("workspaces");
+    if !workspaces_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries =
+        fs::read_dir(&workspaces_dir).map_err(|e| format!("Failed to read workspaces: {}", e))?;
+    let mut workspaces: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| validate_workspace_name(name).is_ok())
+        .collect();
+    workspaces.sort();
+
+    Ok(workspaces)
+}
+
+// Switch every store to another workspace (None = back to the root data
+// directory), then apply that workspace's settings
+#[tauri::command]
+async fn set_workspace(name: Option<String>, app: AppHandle) -> Result<(), String> {
+    if let Some(name) = &name {
+        validate_workspace_name(name)?;
+    }
+
+    {
+        let _guard = DATA_LOCK.lock().await;
+
+        // A debounced task save belongs to the workspace it was made in. In
+        // read-only mode it can't be written anywhere, so it is dropped.
+        let pending = PENDING_TASKS.lock().unwrap().take();
+        if let Some(tasks) = pending {
+            if is_read_only() {
+                log::warn!("Discarding unsaved task edits in read-only mode");
+            } else {
+                save_tasks(tasks, app.clone()).await?;
+            }
+        }
+
+        // Nothing may be created in read-only mode, so only existing
+        // workspaces can be switched to
+        if let Some(name) = &name {
+            let workspace_dir = get_root_data_dir(&app)?.join("workspaces").join(name);
+            if is_read_only() {
+                if !workspace_dir.is_dir() {
+                    return Err(format!("Workspace '{}' does not exist", name));
+                }
+            } else {
+                fs::create_dir_all(workspace_dir)
+                    .map_err(|e| format!("Failed to create workspace directory: {}", e))?;
+            }
+        }
+
+        // In read-only mode the switch only lasts for this run
+        if !is_read_only() {
+            let json = serde_json::to_string_pretty(&name)
+                .map_err(|e| format!("Failed to serialize workspace: {}", e))?;
+            fs::write(get_app_data_dir(&app)?.join("workspace.json"), json)
+                .map_err(|e| format!("Failed to write workspace file: {}", e))?;
+        }
+
+        *ACTIVE_WORKSPACE.lock().unwrap() = name.clone();
+        invalidate_stats_cache();
+        refresh_runtime_settings(&app);
+    }
+
+    log::info!(
+        "Switched to workspace {}",
+        name.as_deref().unwrap_or("(default)")
+    );
+
+    let settings = load_settings(app.clone()).await?;
+    register_global_shortcuts(app.clone(), settings.shortcuts).await?;
+
+    app.emit("workspace-changed", &name)
+        .map_err(|e| format!("Failed to emit workspace change: {}", e))
+}
+
+// Store files below a data root, relative to it: the root's own stores and
+// archives, plus those of every workspace
+fn collect_store_files(root: &std::path::Path) -> Vec<PathBuf> {
+    let is_store = |name: &str| {
+        STORE_FILES.contains(&name) || (name.starts_with("history-") && name.ends_with(".json"))
+    };
+    let stores_in = |relative: PathBuf| -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(root.join(&relative)) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| is_store(name))
+            .map(|name| relative.join(name))
+            .collect()
+    };
+
+    let mut files = stores_in(PathBuf::new());
+    if let Ok(entries) = fs::read_dir(root.join("workspaces")) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if validate_workspace_name(&name).is_ok() {
+                files.extend(stores_in(PathBuf::from("workspaces").join(name)));
+            }
+        }
+    }
+    files
+}
+
+// Copy each file and read it back, undoing every copy if any of them fails
+fn copy_store_files(
+    files: &[PathBuf],
+    from: &std::path::Path,
+    to: &std::path::Path,
+) -> Result<(), String> {
+    let mut copied: Vec<PathBuf> = Vec::new();
+
+    let result = files.iter().try_for_each(|relative| {
+        let source = from.join(relative);
+        let target = to.join(relative);
+        if target.exists() {
+            return Err(format!("{} already exists", target.display()));
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!(
+                    "Cannot create {}: {}",
+                    parent.display(),
+                    describe_io_error(&e)
+                )
+            })?;
+        }
+
+        fs::copy(&source, &target).map_err(|e| {
+            format!(
+                "Cannot copy {}: {}",
+                relative.display(),
+                describe_io_error(&e)
+            )
+        })?;
+        copied.push(target.clone());
+
+        let original = fs::read(&source).map_err(|e| describe_io_error(&e))?;
+        let copy = fs::read(&target).map_err(|e| describe_io_error(&e))?;
+        if original != copy {
+            return Err(format!("Copy of {} doesn't match", relative.display()));
+        }
+        Ok(())
+    });
+
+    if result.is_err() {
+        for path in &copied {
+            let _ = fs::remove_file(path);
+        }
+    }
+    result
+}
+
+// Switch the data directory. With move_existing, every store is copied (and
+// verified) first; if any copy fails nothing changes and the partial copies are
+// removed. Originals are only deleted when delete_originals is set.
+#[tauri::command]
+async fn migrate_data_dir(
+    new_dir: String,
+    move_existing: bool,
+    delete_originals: Option<bool>,
+    app: AppHandle,
+) -> Result<(), String> {
+    ensure_writable()?;
+
+    let new_dir = PathBuf::from(new_dir);
+    if !new_dir.is_absolute() {
+        return Err(format!(
+            "Data directory must be an absolute path: {}",
+            new_dir.display()
+        ));
+    }
+
+    {
+        let _guard = DATA_LOCK.lock().await;
+
+        // Land any debounced task edit in the old directory so it moves too
+        let pending = PENDING_TASKS.lock().unwrap().take();
+        if let Some(tasks) = pending {
+            save_tasks(tasks, app.clone()).await?;
+        }
+
+        let old_dir = get_root_data_dir(&app)?;
+        if old_dir == new_dir {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&new_dir).map_err(|e| {
+            format!(
+                "Cannot create data directory {}: {}",
+                new_dir.display(),
+                describe_io_error(&e)
+            )
+        })?;
+
+        let files = if move_existing {
+            collect_store_files(&old_dir)
+        } else {
+            Vec::new()
+        };
+        copy_store_files(&files, &old_dir, &new_dir)?;
+
+        // Pointing back at the default location simply drops the override
+        let app_data_dir = get_app_data_dir(&app)?;
+        let data_dir = (new_dir != app_data_dir).then(|| new_dir.clone());
+        let json = serde_json::to_string_pretty(&data_dir)
+            .map_err(|e| format!("Failed to serialize data dir: {}", e))?;
+        if let Err(e) = fs::write(app_data_dir.join("data_dir.json"), json) {
+            for relative in &files {
+                let _ = fs::remove_file(new_dir.join(relative));
+            }
+            return Err(format!("Failed to write data dir file: {}", e));
+        }
+        *DATA_DIR_OVERRIDE.lock().unwrap() = data_dir;
+        invalidate_stats_cache();
+
+        if delete_originals.unwrap_or(false) {
+            for relative in &files {
+                if let Err(e) = fs::remove_file(old_dir.join(relative)) {
+                    log::warn!("Failed to delete {}: {}", relative.display(), e);
+                }
+            }
+        }
+    }
+
+    log::info!("Data directory changed to {}", new_dir.display());
+
+    let settings = load_settings(app.clone()).await?;
+    register_global_shortcuts(app.clone(), settings.shortcuts).await?;
+
+    app.emit("data-dir-changed", new_dir.to_string_lossy().to_string())
+        .map_err(|e| format!("Failed to emit data dir change: {}", e))
+}
+
 #[tauri::command]
 async fn save_session_data(session: PomodoroSession, app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    ensure_writable()?;
+
+    let app_data_dir = get_data_dir(&app)?;
 
     // Create the directory if it doesn't exist
     fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
@@ -411,10 +1629,7 @@ async fn save_session_data(session: PomodoroSession, app: AppHandle) -> Result<(
 
 #[tauri::command]
 async fn load_session_data(app: AppHandle) -> Result<Option<PomodoroSession>, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let app_data_dir = get_data_dir(&app)?;
     let file_path = app_data_dir.join("session.json");
 
     if !file_path.exists() {
@@ -429,12 +1644,20 @@ async fn load_session_data(app: AppHandle) -> Result<Option<PomodoroSession>, St
     // Get today's date string
     let today = chrono::Local::now().format("%a %b %d %Y").to_string();
 
+    // Users can opt out of the automatic reset (e.g. to keep counting past midnight)
+    let daily_reset_enabled = load_settings(app.clone())
+        .await
+        .map(|settings| settings.timer.daily_reset_enabled)
+        .unwrap_or(true);
+
     // If the saved session is not from today, reset the counters but keep the date updated
-    if session.date != today {
-        session.completed_pomodoros = 0;
-        session.total_focus_time = 0;
-        session.current_session = 1;
-        session.date = today;
+    if daily_reset_enabled && session.date != today {
+        reset_session_counters(&mut session, today);
+
+        // In read-only mode the reset is only applied in memory
+        if is_read_only() {
+            return Ok(Some(session));
+        }
 
         // Save the reset session back to file
         let json = serde_json::to_string_pretty(&session)
@@ -446,36 +1669,182 @@ async fn load_session_data(app: AppHandle) -> Result<Option<PomodoroSession>, St
     Ok(Some(session))
 }
 
+// The frontend saves this on every start/resume and clears it on pause, stop
+// and completion
 #[tauri::command]
-async fn save_tasks(tasks: Vec<Task>, app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+async fn save_timer_state(state: TimerState, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
 
-    // Create the directory if it doesn't exist
+    chrono::DateTime::parse_from_rfc3339(&state.started_at)
+        .map_err(|e| format!("Invalid start time '{}': {}", state.started_at, e))?;
+
+    let app_data_dir = get_data_dir(&app)?;
     fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
 
-    let file_path = app_data_dir.join("tasks.json");
-    let json = serde_json::to_string_pretty(&tasks)
-        .map_err(|e| format!("Failed to serialize tasks: {}", e))?;
+    let json = serde_json::to_string_pretty(&state)
+        .map_err(|e| format!("Failed to serialize timer state: {}", e))?;
+    fs::write(app_data_dir.join("timer_state.json"), json)
+        .map_err(|e| format!("Failed to write timer state file: {}", e))
+}
 
-    fs::write(file_path, json).map_err(|e| format!("Failed to write tasks file: {}", e))?;
+#[tauri::command]
+async fn clear_timer_state(app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
 
-    // Track tasks saved analytics (if enabled)
-    if are_analytics_enabled(&app).await {
-        let _ = app.track_event("tasks_saved", None);
+    let file_path = get_data_dir(&app)?.join("timer_state.json");
+    if file_path.exists() {
+        fs::remove_file(file_path)
+            .map_err(|e| format!("Failed to delete timer state file: {}", e))?;
     }
-
     Ok(())
 }
 
+// A saved timer is stale when more than its remaining time has passed since
+// it started: the session ended while the app wasn't running, so the UI should
+// ask whether it was finished instead of resuming it.
+#[tauri::command]
+async fn load_timer_state(app: AppHandle) -> Result<Option<LoadedTimerState>, String> {
+    let file_path = get_data_dir(&app)?.join("timer_state.json");
+    if !file_path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read timer state file: {}", e))?;
+    let state: TimerState = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse timer state: {}", e))?;
+
+    let started_at = chrono::DateTime::parse_from_rfc3339(&state.started_at)
+        .map_err(|e| format!("Invalid start time '{}': {}", state.started_at, e))?;
+    // A clock set backwards since the save counts as no time passed
+    let elapsed_secs = (chrono::Local::now().fixed_offset() - started_at)
+        .num_seconds()
+        .max(0) as u64;
+
+    Ok(Some(LoadedTimerState {
+        stale: elapsed_secs > state.remaining_secs as u64,
+        elapsed_secs,
+        state,
+    }))
+}
+
+fn reset_session_counters(session: &mut PomodoroSession, date: String) {
+    session.completed_pomodoros = 0;
+    session.total_focus_time = 0;
+    session.total_focus_time_ms = None;
+    session.current_session = 1;
+    session.date = date;
+    session.notes = None;
+    session.tag_ids = None;
+}
+
+// Explicitly start a new day, for users who turned off the automatic daily reset
+#[tauri::command]
+async fn manual_reset_session(app: AppHandle) -> Result<PomodoroSession, String> {
+    ensure_writable()?;
+
+    let today = chrono::Local::now().format("%a %b %d %Y").to_string();
+    let mut session = load_session_data(app.clone())
+        .await?
+        .unwrap_or_else(|| PomodoroSession {
+            completed_pomodoros: 0,
+            total_focus_time: 0,
+            current_session: 1,
+            date: today.clone(),
+            notes: None,
+            tag_ids: None,
+            total_focus_time_ms: None,
+        });
+
+    reset_session_counters(&mut session, today);
+    save_session_data(session.clone(), app).await?;
+
+    Ok(session)
+}
+
+#[tauri::command]
+async fn save_tasks(tasks: Vec<Task>, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    {
+        // An explicit save supersedes a pending debounced one. Writing under
+        // the pending lock keeps a debounced write from landing after this one.
+        let mut pending = PENDING_TASKS.lock().unwrap();
+        *pending = None;
+        PENDING_TASKS_GENERATION.fetch_add(1, Ordering::SeqCst);
+        write_tasks(&app, &tasks)?;
+    }
+
+    // Track tasks saved analytics (if enabled)
+    if are_analytics_enabled(&app).await {
+        let _ = app.track_event("tasks_saved", None);
+    }
+
+    Ok(())
+}
+
+// Coalesce rapid saves (e.g. one per keystroke) into a single write after a
+// short idle window. Use save_tasks for explicit, immediate saves.
+#[tauri::command]
+async fn save_tasks_debounced(tasks: Vec<Task>, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    *PENDING_TASKS.lock().unwrap() = Some(tasks);
+    let generation = PENDING_TASKS_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    thread::spawn(move || {
+        thread::sleep(TASK_SAVE_DEBOUNCE);
+        // A newer call rescheduled the write
+        if PENDING_TASKS_GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        if let Err(e) = flush_pending_tasks(&app) {
+            log::error!("Failed to write debounced tasks: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+// Write any pending debounced tasks right away (also called on app exit, so
+// this stays synchronous)
+fn flush_pending_tasks(app: &AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let mut pending = PENDING_TASKS.lock().unwrap();
+    let Some(tasks) = pending.take() else {
+        return Ok(());
+    };
+    if let Err(e) = write_tasks(app, &tasks) {
+        // Keep the edits around for the next attempt
+        *pending = Some(tasks);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+fn write_tasks(app: &AppHandle, tasks: &[Task]) -> Result<(), String> {
+    let app_data_dir = get_data_dir(app)?;
+
+    // Create the directory if it doesn't exist
+    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let file_path = app_data_dir.join("tasks.json");
+    let json = serde_json::to_string_pretty(tasks)
+        .map_err(|e| format!("Failed to serialize tasks: {}", e))?;
+
+    fs::write(file_path, json).map_err(|e| format!("Failed to write tasks file: {}", e))
+}
+
 #[tauri::command]
 async fn load_tasks(app: AppHandle) -> Result<Vec<Task>, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    // Debounced edits that haven't been written yet are the latest list
+    if let Some(tasks) = PENDING_TASKS.lock().unwrap().clone() {
+        return Ok(tasks);
+    }
+
+    let app_data_dir = get_data_dir(&app)?;
     let file_path = app_data_dir.join("tasks.json");
 
     if !file_path.exists() {
@@ -490,12 +1859,221 @@ async fn load_tasks(app: AppHandle) -> Result<Vec<Task>, String> {
     Ok(tasks)
 }
 
+#[tauri::command]
+async fn clear_completed_tasks(app: AppHandle) -> Result<u32, String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+
+    // Start from the latest list, including a debounced save that hasn't landed yet
+    let pending = PENDING_TASKS.lock().unwrap().take();
+    let had_pending = pending.is_some();
+    let tasks = match pending {
+        Some(tasks) => tasks,
+        None => load_tasks(app.clone()).await?,
+    };
+
+    let total = tasks.len();
+    let remaining: Vec<Task> = tasks.into_iter().filter(|t| !t.completed).collect();
+    let removed = (total - remaining.len()) as u32;
+
+    if removed > 0 || had_pending {
+        save_tasks(remaining, app).await?;
+    }
+
+    Ok(removed)
+}
+
+// Parse one checklist line into (text, completed). Bullets ("- ", "* ") and
+// Markdown checkboxes ("[ ]", "[x]") are stripped; blank lines yield None.
+fn parse_checklist_line(line: &str) -> Option<(String, bool)> {
+    let mut text = line.trim();
+    if let Some(rest) = text.strip_prefix("- ").or_else(|| text.strip_prefix("* ")) {
+        text = rest.trim_start();
+    }
+
+    let mut completed = false;
+    if let Some(rest) = text.strip_prefix("[ ]") {
+        text = rest.trim_start();
+    } else if let Some(rest) = text
+        .strip_prefix("[x]")
+        .or_else(|| text.strip_prefix("[X]"))
+    {
+        text = rest.trim_start();
+        completed = true;
+    }
+
+    (!text.is_empty()).then(|| (text.to_string(), completed))
+}
+
+#[tauri::command]
+async fn import_tasks_text(text: String, app: AppHandle) -> Result<u32, String> {
+    ensure_writable()?;
+
+    let mut tasks = load_tasks(app.clone()).await?;
+    let now = chrono::Local::now();
+
+    // Millisecond ids like the frontend's Date.now(), bumped to stay unique
+    let mut next_id = tasks
+        .iter()
+        .map(|t| t.id + 1)
+        .max()
+        .unwrap_or(0)
+        .max(now.timestamp_millis() as u64);
+
+    let mut imported = 0;
+    for (text, completed) in text.lines().filter_map(parse_checklist_line) {
+        tasks.push(Task {
+            id: next_id,
+            text,
+            completed,
+            created_at: now.to_rfc3339(),
+            completed_at: completed.then(|| now.to_rfc3339()),
+        });
+        next_id += 1;
+        imported += 1;
+    }
+
+    if imported > 0 {
+        save_tasks(tasks, app).await?;
+    }
+
+    Ok(imported)
+}
+
+const COMPLETION_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+struct CompletionWebhookPayload {
+    mode: String,
+    duration: Option<u32>, // minutes, None when the timer doesn't report it
+    completed_pomodoros: u32,
+    timestamp: String,
+}
+
+// Fire-and-forget POST to the user's automation webhook; failures are only
+// logged so a slow or broken endpoint never holds up the timer
+fn send_completion_webhook(url: String, payload: CompletionWebhookPayload) {
+    tauri::async_runtime::spawn(async move {
+        let client = match reqwest::Client::builder()
+            .timeout(COMPLETION_WEBHOOK_TIMEOUT)
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                log::warn!("Failed to create webhook client: {}", e);
+                return;
+            }
+        };
+
+        match client.post(&url).json(&payload).send().await {
+            Ok(response) if !response.status().is_success() => {
+                log::warn!("Completion webhook returned {}", response.status());
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Completion webhook failed: {}", e.without_url()),
+        }
+    });
+}
+
+// The timer calls this whenever a focus session or break ends, once its data
+// is saved, to run the completion hooks (currently the automation webhook)
+#[tauri::command]
+async fn session_completed(
+    mode: String,
+    duration: Option<u32>,
+    completed_pomodoros: u32,
+    app: AppHandle,
+) -> Result<(), String> {
+    if !matches!(mode.as_str(), "focus" | "break" | "longBreak") {
+        return Err(format!("Unknown session mode '{}'", mode));
+    }
+
+    let settings = load_settings(app).await?;
+    if let Some(url) = settings.advanced.completion_webhook_url {
+        send_completion_webhook(
+            url,
+            CompletionWebhookPayload {
+                mode,
+                duration,
+                completed_pomodoros,
+                timestamp: chrono::Local::now().to_rfc3339(),
+            },
+        );
+    }
+
+    Ok(())
+}
+
+// Persist everything a completed session touches in a single IPC round trip
+#[tauri::command]
+async fn save_session_bundle(bundle: SessionBundle, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+
+    save_session_data(bundle.session.clone(), app.clone()).await?;
+    save_daily_stats(bundle.session, app.clone()).await?;
+
+    if let Some(manual_session) = bundle.manual_session {
+        // Recorded by the timer itself, so its length is never a typo
+        upsert_manual_session(manual_session, Some(true), app.clone()).await?;
+    }
+
+    if !bundle.session_tags.is_empty() {
+        let mut session_tags = load_session_tags(app.clone()).await?;
+        session_tags.extend(bundle.session_tags);
+        write_session_tags(session_tags, app).await?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_task_completion_stats(app: AppHandle) -> Result<TaskStats, String> {
+    let tasks = load_tasks(app).await?;
+    let today = chrono::Local::now().date_naive();
+
+    let total_tasks = tasks.len() as u32;
+    let completed_tasks = tasks.iter().filter(|t| t.completed).count() as u32;
+    let created_today = tasks
+        .iter()
+        .filter(|t| parse_iso_local_date(&t.created_at) == Some(today))
+        .count() as u32;
+
+    // Only tasks with both valid timestamps (in the right order) count towards the average
+    let completion_minutes: Vec<f64> = tasks
+        .iter()
+        .filter(|t| t.completed)
+        .filter_map(|t| {
+            let created = chrono::DateTime::parse_from_rfc3339(&t.created_at).ok()?;
+            let completed =
+                chrono::DateTime::parse_from_rfc3339(t.completed_at.as_deref()?).ok()?;
+            let minutes = (completed - created).num_seconds() as f64 / 60.0;
+            (minutes >= 0.0).then_some(minutes)
+        })
+        .collect();
+
+    Ok(TaskStats {
+        total_tasks,
+        completed_tasks,
+        completion_rate: if total_tasks > 0 {
+            completed_tasks as f64 / total_tasks as f64
+        } else {
+            0.0
+        },
+        avg_minutes_to_complete: if completion_minutes.is_empty() {
+            None
+        } else {
+            Some(completion_minutes.iter().sum::<f64>() / completion_minutes.len() as f64)
+        },
+        created_today,
+    })
+}
+
 #[tauri::command]
 async fn get_stats_history(app: AppHandle) -> Result<Vec<PomodoroSession>, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let app_data_dir = get_data_dir(&app)?;
     let history_path = app_data_dir.join("history.json");
 
     if !history_path.exists() {
@@ -512,10 +2090,9 @@ async fn get_stats_history(app: AppHandle) -> Result<Vec<PomodoroSession>, Strin
 
 #[tauri::command]
 async fn save_daily_stats(session: PomodoroSession, app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    ensure_writable()?;
+
+    let app_data_dir = get_data_dir(&app)?;
 
     // Create the directory if it doesn't exist
     fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
@@ -530,389 +2107,3337 @@ async fn save_daily_stats(session: PomodoroSession, app: AppHandle) -> Result<()
         Vec::new()
     };
 
+    // Keep notes and tags from the existing entry if the update doesn't carry any
+    let mut session = session;
+    if let Some(existing) = history.iter().find(|s| s.date == session.date) {
+        if session.notes.is_none() {
+            session.notes = existing.notes.clone();
+        }
+        if session.tag_ids.is_none() {
+            session.tag_ids = existing.tag_ids.clone();
+        }
+    }
+
+    // The frontend only reports seconds, so grow the stored millisecond total
+    // by the difference instead of re-deriving it from the rounded value
+    if session.total_focus_time_ms.is_none() {
+        let existing = history.iter().find(|s| s.date == session.date);
+        session.total_focus_time_ms = Some(match existing {
+            Some(existing) if session.total_focus_time >= existing.total_focus_time => {
+                existing.focus_time_ms()
+                    + (session.total_focus_time - existing.total_focus_time) as u64 * 1000
+            }
+            _ => session.total_focus_time as u64 * 1000,
+        });
+    }
+
     // Remove existing entry for the same date and add the new one
     history.retain(|s| s.date != session.date);
     history.push(session);
 
-    // Keep only last 30 days
-    history.sort_by(|a, b| a.date.cmp(&b.date));
-    if history.len() > 30 {
-        let start_index = history.len() - 30;
-        history.drain(0..start_index);
-    }
+    apply_history_retention(&mut history);
+
+    let durable = load_settings(app.clone())
+        .await
+        .map(|settings| settings.advanced.durable_writes)
+        .unwrap_or(true);
 
     let json = serde_json::to_string_pretty(&history)
         .map_err(|e| format!("Failed to serialize history: {}", e))?;
-    fs::write(history_path, json).map_err(|e| format!("Failed to write history file: {}", e))?;
+    write_file_atomic(&history_path, &json, durable)
+        .map_err(|e| format!("Failed to write history file: {}", e))?;
+    invalidate_stats_cache();
 
     Ok(())
 }
 
+// Sort history chronologically and keep only the most recent days
+fn apply_history_retention(history: &mut Vec<PomodoroSession>) {
+    history.sort_by_key(|s| parse_session_date(&s.date));
+    if history.len() > HISTORY_RETENTION_DAYS {
+        let start_index = history.len() - HISTORY_RETENTION_DAYS;
+        history.drain(0..start_index);
+    }
+}
+
+// Regenerate history.json entries from the manual session log. This OVERWRITES
+// the derived history for every date that has manual focus sessions; dates
+// without manual sessions are left as they are. Dates that fall outside the
+// retention window are dropped again, so only the rebuilt days that were
+// actually written are counted.
 #[tauri::command]
-async fn update_tray_icon(
-    app: AppHandle,
-    timer_text: String,
-    is_running: bool,
-    session_mode: String,
-    current_session: u32,
-    total_sessions: u32,
-    mode_icon: Option<String>,
-) -> Result<(), String> {
-    use std::sync::{Arc, Mutex};
+async fn rebuild_history_from_sessions(app: AppHandle) -> Result<u32, String> {
+    ensure_writable()?;
 
-    // Use Arc<Mutex<Result<(), String>>> to capture the result from the main thread
-    let result = Arc::new(Mutex::new(Ok(())));
-    let result_clone = Arc::clone(&result);
+    let manual_sessions = load_manual_sessions(app.clone()).await?;
 
-    // Clone the app handle to move into the closure
-    let app_clone = app.clone();
+    // date -> (focus minutes, focus blocks)
+    let mut per_day: HashMap<String, (u32, u32)> = HashMap::new();
+    for session in manual_sessions
+        .iter()
+        .filter(|s| !is_break_session_type(&s.session_type))
+    {
+        let entry = per_day.entry(session.date.clone()).or_insert((0, 0));
+        entry.0 += session.duration;
+        entry.1 += 1;
+    }
 
-    // Move the operation to the main thread using Tauri's app handle
-    // This ensures macOS tray operations run on the main thread
-    app.run_on_main_thread(move || {
-        let mut result_guard = result_clone.lock().unwrap();
-        *result_guard = (|| -> Result<(), String> {
-            if let Some(tray) = app_clone.tray_by_id("main") {
-                // Use the provided mode_icon or fallback to default icons
-                // Using simple text-based icons for better cross-platform support
-                let icon = mode_icon.unwrap_or_else(|| match session_mode.as_str() {
-                    "focus" => "◉".to_string(),        // Focus indicator (filled circle)
-                    "break" => "☼".to_string(),        // Break indicator (sun - daytime rest)
-                    "longBreak" => "☾".to_string(),    // Long break indicator (moon - night rest)
-                    _ => "∞".to_string(),              // Timer fallback (infinity)
-                });
+    let app_data_dir = get_data_dir(&app)?;
+    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
 
-                let status = if is_running { "Running" } else { "Paused" };
-                let title = format!("{} {}", icon, timer_text);
-                tray.set_title(Some(title))
-                    .map_err(|e| format!("Failed to set title: {}", e))?;
+    let mut history = get_stats_history(app.clone()).await?;
+    let rebuilt_dates: BTreeSet<String> = per_day.keys().cloned().collect();
+
+    for (date, (focus_minutes, pomodoros)) in per_day {
+        let existing = history.iter().position(|s| s.date == date);
+        let previous = existing.map(|index| history.remove(index));
+
+        history.push(PomodoroSession {
+            completed_pomodoros: pomodoros,
+            total_focus_time: focus_minutes * 60,
+            current_session: pomodoros + 1,
+            date,
+            notes: previous.as_ref().and_then(|p| p.notes.clone()),
+            tag_ids: previous.and_then(|p| p.tag_ids),
+            total_focus_time_ms: None,
+        });
+    }
 
-                let tooltip = if session_mode == "focus" {
-                    format!(
-                        "Presto - Session {}/{} ({})",
-                        current_session, total_sessions, status
-                    )
-                } else {
-                    format!(
-                        "Presto - {} ({})",
-                        if session_mode == "longBreak" {
-                            "Long Break"
-                        } else {
-                            "Short Break"
-                        },
-                        status
-                    )
-                };
+    apply_history_retention(&mut history);
+    let rebuilt_days = history
+        .iter()
+        .filter(|s| rebuilt_dates.contains(&s.date))
+        .count() as u32;
 
-                tray.set_tooltip(Some(tooltip))
-                    .map_err(|e| format!("Failed to set tooltip: {}", e))?;
-            }
-            Ok(())
-        })();
-    })
-    .map_err(|e| format!("Failed to run on main thread: {}", e))?;
+    let durable = load_settings(app)
+        .await
+        .map(|settings| settings.advanced.durable_writes)
+        .unwrap_or(true);
+
+    let json = serde_json::to_string_pretty(&history)
+        .map_err(|e| format!("Failed to serialize history: {}", e))?;
+    write_file_atomic(&app_data_dir.join("history.json"), &json, durable)
+        .map_err(|e| format!("Failed to write history file: {}", e))?;
+    invalidate_stats_cache();
 
-    // Extract the result from the mutex
-    let final_result = result.lock().unwrap().clone();
-    final_result
+    Ok(rebuilt_days)
 }
 
 #[tauri::command]
-async fn show_window(app: AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("main") {
-        // Check if hide_icon_on_close is enabled to restore dock visibility
-        match load_settings(app.clone()).await {
-            Ok(settings) => {
-                if settings.hide_icon_on_close {
-                    // Restore dock visibility when showing window
-                    #[cfg(target_os = "macos")]
-                    {
-                        let _ = set_dock_visibility(app.clone(), true).await;
-                    }
-                }
-            }
-            Err(_) => {
-                // Ignore error, just proceed with showing window
-            }
+async fn estimate_plan_completion(
+    remaining_sessions: u32,
+    app: AppHandle,
+) -> Result<PlanEstimate, String> {
+    let settings = load_settings(app.clone()).await?;
+    let completed_today = load_session_data(app)
+        .await?
+        .map(|s| s.completed_pomodoros)
+        .unwrap_or(0);
+
+    let mut total_focus_minutes = 0;
+    let mut total_break_minutes = 0;
+    let mut long_breaks = 0;
+
+    for i in 1..=remaining_sessions {
+        total_focus_minutes += settings.timer.focus_duration;
+
+        // No break is needed after the final session of the plan
+        if i == remaining_sessions {
+            break;
         }
 
-        window
-            .show()
-            .map_err(|e| format!("Failed to show window: {}", e))?;
-        window
-            .set_focus()
-            .map_err(|e| format!("Failed to focus window: {}", e))?;
+        if (completed_today + i) % settings.timer.long_break_interval() == 0 {
+            total_break_minutes += settings.timer.long_break_duration;
+            long_breaks += 1;
+        } else {
+            total_break_minutes += settings.timer.break_duration;
+        }
     }
-    Ok(())
-}
 
-#[tauri::command]
-async fn save_settings(settings: AppSettings, app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let finish_time = chrono::Local::now()
+        + chrono::Duration::minutes((total_focus_minutes + total_break_minutes) as i64);
 
-    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    Ok(PlanEstimate {
+        finish_time: finish_time.to_rfc3339(),
+        total_focus_minutes,
+        total_break_minutes,
+        long_breaks,
+    })
+}
 
-    let file_path = app_data_dir.join("settings.json");
-    let json = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+// Parse the date format used by the frontend (e.g. "Mon Oct 16 2026")
+fn parse_session_date(date: &str) -> Option<chrono::NaiveDate> {
+    chrono::NaiveDate::parse_from_str(date, "%a %b %d %Y").ok()
+}
 
-    fs::write(file_path, json).map_err(|e| format!("Failed to write settings file: {}", e))?;
+// Format a date the same way the frontend's Date.toDateString() does
+fn format_session_date(date: chrono::NaiveDate) -> String {
+    date.format("%a %b %d %Y").to_string()
+}
 
-    Ok(())
+// Accept either a frontend date key or an ISO "YYYY-MM-DD" date from commands
+fn parse_date_arg(date: &str) -> Result<chrono::NaiveDate, String> {
+    parse_session_date(date)
+        .or_else(|| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+        .ok_or_else(|| format!("Invalid date '{}'", date))
 }
 
+// Stats for an arbitrary set of dates in one call; dates without data map to zeros.
+// Completed timer sessions are logged in both history.json and the manual
+// session log, so the two sources are merged per date with the larger value
+// instead of being added up.
 #[tauri::command]
-async fn load_settings(app: AppHandle) -> Result<AppSettings, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
-    let file_path = app_data_dir.join("settings.json");
+async fn get_stats_for_dates(
+    dates: Vec<String>,
+    app: AppHandle,
+) -> Result<HashMap<String, DayStat>, String> {
+    let history = get_stats_history(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app).await?;
+    let focus_minutes = daily_focus_minutes(&manual_sessions);
+    let pomodoros = daily_focus_sessions(&manual_sessions);
+
+    let history_by_date: HashMap<chrono::NaiveDate, &PomodoroSession> = history
+        .iter()
+        .filter_map(|s| parse_session_date(&s.date).map(|date| (date, s)))
+        .collect();
+
+    dates
+        .into_iter()
+        .map(|key| {
+            let date = parse_date_arg(&key)?;
+            let (history_pomodoros, history_minutes) = history_by_date
+                .get(&date)
+                .map_or((0, 0), |s| (s.completed_pomodoros, s.total_focus_time / 60));
+            let stat = DayStat {
+                pomodoros: pomodoros
+                    .get(&date)
+                    .copied()
+                    .unwrap_or(0)
+                    .max(history_pomodoros),
+                focus_minutes: focus_minutes
+                    .get(&date)
+                    .copied()
+                    .unwrap_or(0)
+                    .max(history_minutes),
+            };
+            Ok((key, stat))
+        })
+        .collect()
+}
 
-    if !file_path.exists() {
-        return Ok(AppSettings::default());
+// Trailing `window`-day average of focus minutes for each of the last `days`
+// days, oldest first. The window never reaches before the first day of the
+// range, so the earliest days average over fewer days instead of being dropped.
+#[tauri::command]
+async fn get_rolling_average(
+    days: u32,
+    window: u32,
+    app: AppHandle,
+) -> Result<Vec<DayValue>, String> {
+    if window == 0 {
+        return Err("Window must be at least 1 day".to_string());
     }
 
-    let contents = fs::read_to_string(file_path)
-        .map_err(|e| format!("Failed to read settings file: {}", e))?;
-    let settings: AppSettings =
-        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    let daily_minutes = load_daily_focus_minutes(&app).await?;
+    let today = chrono::Local::now().date_naive();
+    let range: Vec<u32> = (0..days)
+        .rev()
+        .map(|offset| {
+            let date = today - chrono::Duration::days(offset as i64);
+            daily_minutes.get(&date).copied().unwrap_or(0)
+        })
+        .collect();
+
+    Ok(range
+        .iter()
+        .enumerate()
+        .map(|(i, _)| {
+            let window_start = (i + 1).saturating_sub(window as usize);
+            let values = &range[window_start..=i];
+            let total: u32 = values.iter().sum();
+            let date = today - chrono::Duration::days((range.len() - 1 - i) as i64);
+            DayValue {
+                date: format_session_date(date),
+                value: total as f64 / values.len() as f64,
+            }
+        })
+        .collect())
+}
 
-    Ok(settings)
+#[tauri::command]
+async fn get_active_dates(from: String, to: String, app: AppHandle) -> Result<Vec<String>, String> {
+    let from = parse_date_arg(&from)?;
+    let to = parse_date_arg(&to)?;
+
+    let history = get_stats_history(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app).await?;
+
+    let history_dates = history
+        .iter()
+        .filter(|s| s.completed_pomodoros > 0)
+        .filter_map(|s| parse_session_date(&s.date));
+    let manual_dates = manual_sessions
+        .iter()
+        .filter_map(|s| parse_session_date(&s.date));
+
+    // BTreeSet dedupes and keeps the dates in ascending order
+    let active_dates: BTreeSet<chrono::NaiveDate> = history_dates
+        .chain(manual_dates)
+        .filter(|date| *date >= from && *date <= to)
+        .collect();
+
+    Ok(active_dates.into_iter().map(format_session_date).collect())
 }
 
 #[tauri::command]
-async fn register_global_shortcuts(
-    app: AppHandle,
-    shortcuts: ShortcutSettings,
-) -> Result<(), String> {
-    // Unregister all existing shortcuts first
-    app.global_shortcut()
-        .unregister_all()
-        .map_err(|e| format!("Failed to unregister shortcuts: {}", e))?;
+async fn archive_history(app: AppHandle) -> Result<Vec<String>, String> {
+    ensure_writable()?;
 
-    // Register start/stop shortcut
-    if let Some(ref shortcut_str) = shortcuts.start_stop {
-        let shortcut: Shortcut = shortcut_str
-            .parse()
-            .map_err(|e| format!("Invalid start/stop shortcut '{}': {}", shortcut_str, e))?;
+    let app_data_dir = get_data_dir(&app)?;
 
-        let app_handle = app.clone();
-        app.global_shortcut()
-            .on_shortcut(shortcut, move |_app, _shortcut, _event| {
-                if !should_debounce_shortcut("start-stop") {
-                    let _ = app_handle.emit("global-shortcut", "start-stop");
-                }
-            })
-            .map_err(|e| format!("Failed to register start/stop shortcut: {}", e))?;
+    let history_path = app_data_dir.join("history.json");
+    if !history_path.exists() {
+        return Ok(Vec::new());
     }
 
-    // Register reset shortcut
-    if let Some(ref shortcut_str) = shortcuts.reset {
-        let shortcut: Shortcut = shortcut_str
-            .parse()
-            .map_err(|e| format!("Invalid reset shortcut '{}': {}", shortcut_str, e))?;
+    let history = get_stats_history(app.clone()).await?;
+    let current_year = chrono::Local::now().year();
+    let durable = load_settings(app)
+        .await
+        .map(|settings| settings.advanced.durable_writes)
+        .unwrap_or(true);
+
+    // Split entries into the ones to keep and the ones to archive, grouped by year
+    let mut kept: Vec<PomodoroSession> = Vec::new();
+    let mut by_year: BTreeMap<i32, Vec<PomodoroSession>> = BTreeMap::new();
+    for session in history {
+        match parse_session_date(&session.date) {
+            Some(date) if date.year() < current_year => {
+                by_year.entry(date.year()).or_default().push(session)
+            }
+            _ => kept.push(session),
+        }
+    }
 
-        let app_handle = app.clone();
-        app.global_shortcut()
-            .on_shortcut(shortcut, move |_app, _shortcut, _event| {
-                if !should_debounce_shortcut("reset") {
-                    let _ = app_handle.emit("global-shortcut", "reset");
-                }
-            })
-            .map_err(|e| format!("Failed to register reset shortcut: {}", e))?;
+    let mut archived_files = Vec::new();
+    for (year, sessions) in by_year {
+        let file_name = format!("history-{}.json", year);
+        let archive_path = app_data_dir.join(&file_name);
+
+        // Merge with an existing archive, newer entries win for the same date.
+        // An unreadable archive aborts rather than being overwritten; years
+        // already archived stay in history.json too, so a retry loses nothing.
+        let mut archive: Vec<PomodoroSession> = if archive_path.exists() {
+            let content = fs::read_to_string(&archive_path)
+                .map_err(|e| format!("Failed to read {}: {}", file_name, e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse {}: {}", file_name, e))?
+        } else {
+            Vec::new()
+        };
+        archive.retain(|a| !sessions.iter().any(|s| s.date == a.date));
+        archive.extend(sessions);
+        archive.sort_by_key(|s| parse_session_date(&s.date));
+
+        let json = serde_json::to_string_pretty(&archive)
+            .map_err(|e| format!("Failed to serialize {}: {}", file_name, e))?;
+        write_file_atomic(&archive_path, &json, durable)
+            .map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+
+        archived_files.push(file_name);
     }
 
-    // Register skip shortcut
-    if let Some(ref shortcut_str) = shortcuts.skip {
-        let shortcut: Shortcut = shortcut_str
-            .parse()
-            .map_err(|e| format!("Invalid skip shortcut '{}': {}", shortcut_str, e))?;
+    // Only rewrite the hot file if something was actually moved out
+    if !archived_files.is_empty() {
+        let json = serde_json::to_string_pretty(&kept)
+            .map_err(|e| format!("Failed to serialize history: {}", e))?;
+        write_file_atomic(&history_path, &json, durable)
+            .map_err(|e| format!("Failed to write history file: {}", e))?;
+        invalidate_stats_cache();
+    }
 
-        let app_handle = app.clone();
-        app.global_shortcut()
-            .on_shortcut(shortcut, move |_app, _shortcut, _event| {
-                if !should_debounce_shortcut("skip") {
-                    let _ = app_handle.emit("global-shortcut", "skip");
-                }
-            })
-            .map_err(|e| format!("Failed to register skip shortcut: {}", e))?;
+    Ok(archived_files)
+}
+
+#[tauri::command]
+async fn load_history_archive(year: u32, app: AppHandle) -> Result<Vec<PomodoroSession>, String> {
+    let app_data_dir = get_data_dir(&app)?;
+    let archive_path = app_data_dir.join(format!("history-{}.json", year));
+
+    if !archive_path.exists() {
+        return Ok(Vec::new());
     }
 
-    // Emit an event to the frontend to update local shortcuts as well
-    app.emit("shortcuts-updated", &shortcuts)
-        .map_err(|e| format!("Failed to emit shortcuts update: {}", e))?;
+    let content = fs::read_to_string(archive_path)
+        .map_err(|e| format!("Failed to read history archive: {}", e))?;
+    let history: Vec<PomodoroSession> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse history archive: {}", e))?;
 
-    Ok(())
+    Ok(history)
+}
+
+// Compute (current, longest) streaks from a set of active days.
+// Days are stepped with succ_opt/pred_opt instead of adding 86400 seconds,
+// so DST transitions and leap days never create phantom gaps.
+fn compute_streaks(
+    active_dates: &BTreeSet<chrono::NaiveDate>,
+    today: chrono::NaiveDate,
+) -> (u32, u32) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<chrono::NaiveDate> = None;
+    for date in active_dates {
+        run = match previous {
+            Some(prev) if prev.succ_opt() == Some(*date) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*date);
+    }
+
+    // The current streak stays alive until the end of today, so start from
+    // yesterday if nothing has been recorded yet today
+    let mut cursor = if active_dates.contains(&today) {
+        Some(today)
+    } else {
+        today.pred_opt()
+    };
+    let mut current = 0;
+    while let Some(date) = cursor {
+        if !active_dates.contains(&date) {
+            break;
+        }
+        current += 1;
+        cursor = date.pred_opt();
+    }
+
+    (current, longest)
+}
+
+// Days that count towards a focus streak
+async fn load_streak_dates(app: &AppHandle) -> Result<BTreeSet<chrono::NaiveDate>, String> {
+    let history = get_stats_history(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app.clone()).await?;
+    Ok(streak_dates(&history, &manual_sessions))
+}
+
+fn streak_dates(
+    history: &[PomodoroSession],
+    manual_sessions: &[ManualSession],
+) -> BTreeSet<chrono::NaiveDate> {
+    let mut active_dates: BTreeSet<chrono::NaiveDate> = history
+        .iter()
+        .filter(|s| s.completed_pomodoros > 0 || s.focus_time_ms() > 0)
+        .filter_map(|s| parse_session_date(&s.date))
+        .collect();
+
+    // Manual break sessions don't count towards a focus streak
+    active_dates.extend(
+        manual_sessions
+            .iter()
+            .filter(|s| !is_break_session_type(&s.session_type))
+            .filter_map(|s| parse_session_date(&s.date)),
+    );
+
+    active_dates
+}
+
+fn streak_info(active_dates: &BTreeSet<chrono::NaiveDate>, today: chrono::NaiveDate) -> StreakInfo {
+    let (current_streak, longest_streak) = compute_streaks(active_dates, today);
+
+    StreakInfo {
+        current_streak,
+        longest_streak,
+        last_active_date: active_dates
+            .iter()
+            .next_back()
+            .map(|d| format_session_date(*d)),
+    }
 }
 
 #[tauri::command]
-async fn unregister_global_shortcuts(app: AppHandle) -> Result<(), String> {
-    app.global_shortcut()
-        .unregister_all()
-        .map_err(|e| format!("Failed to unregister shortcuts: {}", e))?;
-    Ok(())
+async fn get_streak_info(app: AppHandle) -> Result<StreakInfo, String> {
+    let active_dates = load_streak_dates(&app).await?;
+    Ok(streak_info(
+        &active_dates,
+        chrono::Local::now().date_naive(),
+    ))
 }
 
+// Focus minutes still needed today to keep the streak going. Any recorded
+// focus makes a day count, so this is 0 once today is active and one
+// pomodoro's worth of minutes otherwise.
 #[tauri::command]
-async fn reset_all_data(app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+async fn streak_min_minutes_today(app: AppHandle) -> Result<u32, String> {
+    let active_dates = load_streak_dates(&app).await?;
+    let today = chrono::Local::now().date_naive();
 
-    let files_to_delete = vec![
-        "session.json",
-        "tasks.json",
-        "history.json",
-        "settings.json",
-        "manual_sessions.json",
-    ];
+    if active_dates.contains(&today) {
+        return Ok(0);
+    }
 
-    for file_name in files_to_delete {
-        let file_path = app_data_dir.join(file_name);
-        if file_path.exists() {
-            fs::remove_file(file_path)
-                .map_err(|e| format!("Failed to delete {}: {}", file_name, e))?;
+    Ok(load_settings(app).await?.timer.focus_duration)
+}
+
+// True if `date` falls in the `days`-day window ending today (inclusive)
+fn within_last_days(date: chrono::NaiveDate, today: chrono::NaiveDate, days: u32) -> bool {
+    date <= today && date > today - chrono::Duration::days(days as i64)
+}
+
+fn is_break_session_type(session_type: &str) -> bool {
+    session_type == "break" || session_type == "longBreak"
+}
+
+// Session type filter shared by the aggregation commands; empty means every type
+fn matches_session_types(types: &[String], session_type: &str) -> bool {
+    types.is_empty() || types.iter().any(|t| t == session_type)
+}
+
+// Focus minutes per day. Every finished timer session is also saved as a manual
+// focus session, so manual sessions alone cover both; adding the timer history
+// on top would count timer focus twice.
+fn daily_focus_minutes(manual_sessions: &[ManualSession]) -> BTreeMap<chrono::NaiveDate, u32> {
+    let mut minutes: BTreeMap<chrono::NaiveDate, u32> = BTreeMap::new();
+
+    for session in manual_sessions
+        .iter()
+        .filter(|s| !is_break_session_type(&s.session_type))
+    {
+        if let Some(date) = parse_session_date(&session.date) {
+            *minutes.entry(date).or_default() += session.duration;
         }
     }
 
-    /*
-    if app_data_dir.exists() {
-        let _ = fs::remove_dir(&app_data_dir);
+    minutes
+}
+
+// Focus sessions per day, from manual sessions only for the same reason
+fn daily_focus_sessions(manual_sessions: &[ManualSession]) -> BTreeMap<chrono::NaiveDate, u32> {
+    let mut sessions: BTreeMap<chrono::NaiveDate, u32> = BTreeMap::new();
+
+    for session in manual_sessions
+        .iter()
+        .filter(|s| !is_break_session_type(&s.session_type))
+    {
+        if let Some(date) = parse_session_date(&session.date) {
+            *sessions.entry(date).or_default() += 1;
+        }
     }
-    */
 
-    Ok(())
+    sessions
 }
 
-#[tauri::command]
-async fn enable_autostart(app: AppHandle) -> Result<(), String> {
-    let autostart_manager = app.autolaunch();
-    autostart_manager
-        .enable()
-        .map_err(|e| format!("Failed to enable autostart: {}", e))?;
-    Ok(())
+async fn load_daily_focus_minutes(
+    app: &AppHandle,
+) -> Result<BTreeMap<chrono::NaiveDate, u32>, String> {
+    let manual_sessions = load_manual_sessions(app.clone()).await?;
+    Ok(daily_focus_minutes(&manual_sessions))
+}
+
+// First day of the week containing `date`, for weeks starting on `week_start`
+fn start_of_week(date: chrono::NaiveDate, week_start: chrono::Weekday) -> chrono::NaiveDate {
+    let days_into_week =
+        (date.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7;
+    date - chrono::Duration::days(days_into_week as i64)
+}
+
+fn weekday_name(weekday: chrono::Weekday) -> &'static str {
+    match weekday {
+        chrono::Weekday::Mon => "Monday",
+        chrono::Weekday::Tue => "Tuesday",
+        chrono::Weekday::Wed => "Wednesday",
+        chrono::Weekday::Thu => "Thursday",
+        chrono::Weekday::Fri => "Friday",
+        chrono::Weekday::Sat => "Saturday",
+        chrono::Weekday::Sun => "Sunday",
+    }
 }
 
 #[tauri::command]
-async fn disable_autostart(app: AppHandle) -> Result<(), String> {
-    let autostart_manager = app.autolaunch();
-    autostart_manager
-        .disable()
-        .map_err(|e| format!("Failed to disable autostart: {}", e))?;
-    Ok(())
+async fn get_weekday_averages(
+    weeks: u32,
+    include_empty: Option<bool>,
+    app: AppHandle,
+) -> Result<[WeekdayAvg; 7], String> {
+    if weeks == 0 {
+        return Err("Weeks must be at least 1".to_string());
+    }
+    // history.json only keeps HISTORY_RETENTION_DAYS days, so a longer window
+    // would divide by weeks that can't have data
+    let weeks = weeks.min((HISTORY_RETENTION_DAYS / 7) as u32);
+    let include_empty = include_empty.unwrap_or(false);
+    let history = get_stats_history(app).await?;
+
+    let today = chrono::Local::now().date_naive();
+
+    // Index 0 is Monday, matching chrono's num_days_from_monday
+    let mut focus_ms = [0u64; 7];
+    let mut pomodoros = [0u64; 7];
+    let mut days_with_data = [0u32; 7];
+
+    for session in &history {
+        let Some(date) = parse_session_date(&session.date) else {
+            continue;
+        };
+        if !within_last_days(date, today, weeks * 7) {
+            continue;
+        }
+
+        let index = date.weekday().num_days_from_monday() as usize;
+        focus_ms[index] += session.focus_time_ms();
+        pomodoros[index] += session.completed_pomodoros as u64;
+        if session.focus_time_ms() > 0 || session.completed_pomodoros > 0 {
+            days_with_data[index] += 1;
+        }
+    }
+
+    let mut averages: [WeekdayAvg; 7] = Default::default();
+    let mut weekday = chrono::Weekday::Mon;
+    for (index, average) in averages.iter_mut().enumerate() {
+        // Either every week in the window counts, or only the ones with activity
+        let divisor = if include_empty {
+            weeks
+        } else {
+            days_with_data[index]
+        };
+
+        *average = WeekdayAvg {
+            weekday: weekday_name(weekday).to_string(),
+            avg_focus_minutes: if divisor > 0 {
+                focus_ms[index] as f64 / 60_000.0 / divisor as f64
+            } else {
+                0.0
+            },
+            avg_pomodoros: if divisor > 0 {
+                pomodoros[index] as f64 / divisor as f64
+            } else {
+                0.0
+            },
+            days_with_data: days_with_data[index],
+        };
+        weekday = weekday.succ();
+    }
+
+    Ok(averages)
 }
 
 #[tauri::command]
-async fn is_autostart_enabled(app: AppHandle) -> Result<bool, String> {
-    let autostart_manager = app.autolaunch();
-    autostart_manager
-        .is_enabled()
-        .map_err(|e| format!("Failed to check autostart status: {}", e))
+async fn get_focus_break_ratio(
+    days: u32,
+    types: Option<Vec<String>>,
+    app: AppHandle,
+) -> Result<FocusBreakRatio, String> {
+    let types = types.unwrap_or_default();
+    let settings = load_settings(app.clone()).await?;
+    let history = get_stats_history(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app).await?;
+    let today = chrono::Local::now().date_naive();
+
+    let mut total_focus_minutes = 0;
+    let mut total_break_minutes = 0;
+
+    // Timer breaks are never recorded, so derive the ones that followed each
+    // completed pomodoro from the configured durations. Timer focus time is
+    // left out here: every timer session is also saved as a manual focus
+    // session, which is counted below.
+    for session in &history {
+        let Some(date) = parse_session_date(&session.date) else {
+            continue;
+        };
+        if !within_last_days(date, today, days) {
+            continue;
+        }
+
+        let long_breaks = session.completed_pomodoros / settings.timer.long_break_interval();
+        let short_breaks = session.completed_pomodoros - long_breaks;
+        total_break_minutes += long_breaks * settings.timer.long_break_duration
+            + short_breaks * settings.timer.break_duration;
+    }
+
+    for session in manual_sessions
+        .iter()
+        .filter(|s| matches_session_types(&types, &s.session_type))
+    {
+        let Some(date) = parse_session_date(&session.date) else {
+            continue;
+        };
+        if !within_last_days(date, today, days) {
+            continue;
+        }
+
+        if is_break_session_type(&session.session_type) {
+            total_break_minutes += session.duration;
+        } else {
+            total_focus_minutes += session.duration;
+        }
+    }
+
+    let ratio = if total_break_minutes > 0 {
+        Some(total_focus_minutes as f64 / total_break_minutes as f64)
+    } else {
+        None
+    };
+
+    Ok(FocusBreakRatio {
+        total_focus_minutes,
+        total_break_minutes,
+        ratio,
+    })
+}
+
+// Append a note line, keeping whatever was written before
+fn append_note(existing: Option<String>, note: &str) -> Option<String> {
+    match existing {
+        Some(existing) if !existing.is_empty() => Some(format!("{}\n{}", existing, note)),
+        _ => Some(note.to_string()),
+    }
 }
 
 #[tauri::command]
-async fn save_manual_sessions(sessions: Vec<ManualSession>, app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+async fn add_session_note(date: String, note: String, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let mut updated = false;
+
+    // Today's live session
+    if let Some(mut session) = load_session_data(app.clone()).await? {
+        if session.date == date {
+            session.notes = append_note(session.notes.take(), &note);
+            save_session_data(session.clone(), app.clone()).await?;
+            save_daily_stats(session, app.clone()).await?;
+            updated = true;
+        }
+    }
 
-    // Create the directory if it doesn't exist
-    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    // A past day in the history
+    if !updated {
+        let history = get_stats_history(app.clone()).await?;
+        if let Some(mut session) = history.into_iter().find(|s| s.date == date) {
+            session.notes = append_note(session.notes.take(), &note);
+            save_daily_stats(session, app).await?;
+            updated = true;
+        }
+    }
 
-    let file_path = app_data_dir.join("manual_sessions.json");
-    let json = serde_json::to_string_pretty(&sessions)
-        .map_err(|e| format!("Failed to serialize manual sessions: {}", e))?;
+    if updated {
+        Ok(())
+    } else {
+        Err(format!("No pomodoro session found for {}", date))
+    }
+}
 
-    fs::write(file_path, json)
-        .map_err(|e| format!("Failed to write manual sessions file: {}", e))?;
+const TRAY_TITLE_PLACEHOLDERS: &[&str] = &["icon", "time", "mode", "session", "total"];
 
-    // Track manual sessions saved analytics (if enabled)
-    if are_analytics_enabled(&app).await {
-        let properties = Some(serde_json::json!({
-            "session_count": sessions.len()
-        }));
-        let _ = app.track_event("manual_sessions_saved", properties);
+// Substitute {placeholder}s in a tray title format, rejecting unknown ones
+fn render_tray_title(format: &str, values: &HashMap<&str, String>) -> Result<String, String> {
+    let mut title = String::new();
+    let mut rest = format;
+
+    while let Some(start) = rest.find('{') {
+        title.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err(format!(
+                "Unclosed placeholder in tray title format '{}'",
+                format
+            ));
+        };
+
+        let name = &rest[start + 1..start + end];
+        if !TRAY_TITLE_PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "Unknown placeholder '{{{}}}' in tray title format (allowed: {})",
+                name,
+                TRAY_TITLE_PLACEHOLDERS
+                    .iter()
+                    .map(|p| format!("{{{}}}", p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        title.push_str(values.get(name).map(String::as_str).unwrap_or_default());
+        rest = &rest[start + end + 1..];
     }
+    title.push_str(rest);
 
-    Ok(())
+    Ok(title)
 }
 
+// Average share (0-1) of the daily session target actually completed over the
+// last `days` days. History doesn't record the target each day had, so the
+// current total_sessions is assumed for the whole period. Only days with a
+// history entry count, and each day is capped at 1 so an extra-long day can't
+// hide a missed one.
 #[tauri::command]
-async fn load_manual_sessions(app: AppHandle) -> Result<Vec<ManualSession>, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
-    let file_path = app_data_dir.join("manual_sessions.json");
+async fn get_plan_completion_rate(days: u32, app: AppHandle) -> Result<f64, String> {
+    let target = load_settings(app.clone()).await?.timer.total_sessions;
+    if target == 0 {
+        return Ok(1.0);
+    }
 
-    if !file_path.exists() {
-        return Ok(Vec::new());
+    let history = get_stats_history(app).await?;
+    let today = chrono::Local::now().date_naive();
+    let ratios: Vec<f64> = history
+        .iter()
+        .filter(|s| {
+            parse_session_date(&s.date)
+                .map(|date| within_last_days(date, today, days))
+                .unwrap_or(false)
+        })
+        .map(|s| (s.completed_pomodoros as f64 / target as f64).min(1.0))
+        .collect();
+
+    if ratios.is_empty() {
+        return Ok(0.0);
     }
+    Ok(ratios.iter().sum::<f64>() / ratios.len() as f64)
+}
 
-    let content = fs::read_to_string(file_path)
-        .map_err(|e| format!("Failed to read manual sessions file: {}", e))?;
-    let sessions: Vec<ManualSession> = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse manual sessions: {}", e))?;
+#[tauri::command]
+async fn get_goal_pace(app: AppHandle) -> Result<GoalPace, String> {
+    let settings = load_settings(app.clone()).await?;
+    let daily_minutes = load_daily_focus_minutes(&app).await?;
+
+    Ok(goal_pace(
+        &settings.timer,
+        &daily_minutes,
+        chrono::Local::now().date_naive(),
+    ))
+}
 
-    Ok(sessions)
+fn goal_pace(
+    timer: &TimerSettings,
+    daily_minutes: &BTreeMap<chrono::NaiveDate, u32>,
+    today: chrono::NaiveDate,
+) -> GoalPace {
+    let weekly_goal_minutes = timer.weekly_goal_minutes;
+    let week_start = start_of_week(today, timer.week_start());
+    let minutes_done: u32 = daily_minutes
+        .range(week_start..=today)
+        .map(|(_, m)| m)
+        .sum();
+
+    let minutes_remaining = weekly_goal_minutes.saturating_sub(minutes_done);
+
+    // Today counts as a remaining day, so on the last day this is the full remainder
+    let remaining_days = 7 - (today - week_start).num_days() as u32;
+
+    GoalPace {
+        weekly_goal_minutes,
+        minutes_done,
+        minutes_remaining,
+        remaining_days,
+        minutes_per_remaining_day: minutes_remaining.div_ceil(remaining_days),
+    }
 }
 
+// Projected focus minutes for the whole week at this week's pace so far. Pace
+// is measured per hour elapsed since the week began rather than per full day, so
+// the first day of the week projects too; the first hour counts as a full hour
+// to keep a session right after midnight from projecting a huge total.
 #[tauri::command]
-async fn save_manual_session(session: ManualSession, app: AppHandle) -> Result<(), String> {
-    // Load existing sessions
-    let mut sessions = load_manual_sessions(app.clone()).await?;
+async fn project_week_total(app: AppHandle) -> Result<u32, String> {
+    const HOURS_PER_WEEK: f64 = 7.0 * 24.0;
 
-    // Remove existing session with same ID if it exists (for updates)
-    sessions.retain(|s| s.id != session.id);
+    let settings = load_settings(app.clone()).await?;
+    let daily_minutes = load_daily_focus_minutes(&app).await?;
 
-    // Add the new/updated session
-    sessions.push(session);
+    let now = chrono::Local::now().naive_local();
+    let today = now.date();
+    let week_start = start_of_week(today, settings.timer.week_start());
+    let minutes_so_far: u32 = daily_minutes
+        .range(week_start..=today)
+        .map(|(_, m)| m)
+        .sum();
 
-    // Save all sessions back
-    save_manual_sessions(sessions, app).await
+    let hours_elapsed = ((now - week_start.and_time(chrono::NaiveTime::MIN)).num_seconds() as f64
+        / 3600.0)
+        .max(1.0);
+
+    Ok((minutes_so_far as f64 / hours_elapsed * HOURS_PER_WEEK).round() as u32)
 }
 
 #[tauri::command]
-async fn delete_manual_session(session_id: String, app: AppHandle) -> Result<(), String> {
-    // Load existing sessions
-    let mut sessions = load_manual_sessions(app.clone()).await?;
+async fn get_daily_goal_progress(app: AppHandle) -> Result<DailyGoal, String> {
+    let settings = load_settings(app.clone()).await?;
+    let daily_minutes = load_daily_focus_minutes(&app).await?;
+
+    let today = chrono::Local::now().date_naive();
+    let goal_minutes = settings.timer.daily_goal_minutes();
+    let completed_minutes = daily_minutes.get(&today).copied().unwrap_or(0);
+
+    Ok(DailyGoal {
+        goal_minutes,
+        completed_minutes,
+        // A zero goal is met by definition
+        percent: if goal_minutes > 0 {
+            completed_minutes as f64 / goal_minutes as f64 * 100.0
+        } else {
+            100.0
+        },
+        met: completed_minutes >= goal_minutes,
+    })
+}
 
-    // Remove the session with the specified ID
-    sessions.retain(|s| s.id != session_id);
+// Same week the weekly goal uses, so the UI never disagrees with it. The week
+// number is the ISO week of the week's Monday, which is the week's first day
+// for Monday weeks and its second for Sunday weeks.
+#[tauri::command]
+async fn get_current_week(app: AppHandle) -> Result<WeekInfo, String> {
+    let settings = load_settings(app).await?;
+    let today = chrono::Local::now().date_naive();
+    Ok(week_info(today, settings.timer.week_start()))
+}
 
-    // Save the updated sessions back
-    save_manual_sessions(sessions, app).await
+fn week_info(date: chrono::NaiveDate, week_start: chrono::Weekday) -> WeekInfo {
+    let week_start = start_of_week(date, week_start);
+    let monday = start_of_week(week_start + chrono::Duration::days(6), chrono::Weekday::Mon);
+    let iso_week = monday.iso_week();
+
+    WeekInfo {
+        iso_week: iso_week.week(),
+        year: iso_week.year(),
+        start_date: format_session_date(week_start),
+        end_date: format_session_date(week_start + chrono::Duration::days(6)),
+    }
+}
+
+// 0-100 score for how evenly focus is spread over the given days (zero days
+// included). With mean m and population standard deviation s of the daily
+// minutes, the coefficient of variation cv = s / m ranges from 0 (every day the
+// same) to sqrt(n - 1) (everything on a single day), so
+//     score = 100 * (1 - cv / sqrt(n - 1))
+// No focus at all scores 0; a single day with focus scores 100.
+fn consistency_score(daily_minutes: &[u32]) -> f64 {
+    let n = daily_minutes.len() as f64;
+    let mean = daily_minutes.iter().map(|&m| m as f64).sum::<f64>() / n;
+    if daily_minutes.is_empty() || mean == 0.0 {
+        return 0.0;
+    }
+    if daily_minutes.len() == 1 {
+        return 100.0;
+    }
+
+    let variance = daily_minutes
+        .iter()
+        .map(|&m| (m as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    let cv = variance.sqrt() / mean;
+
+    (100.0 * (1.0 - cv / (n - 1.0).sqrt())).clamp(0.0, 100.0)
 }
 
 #[tauri::command]
-async fn get_manual_sessions_for_date(
-    date: String,
-    app: AppHandle,
-) -> Result<Vec<ManualSession>, String> {
-    let sessions = load_manual_sessions(app).await?;
+async fn get_consistency_score(days: u32, app: AppHandle) -> Result<f64, String> {
+    let daily_minutes = load_daily_focus_minutes(&app).await?;
+    let today = chrono::Local::now().date_naive();
 
-    // Filter sessions for the specified date
-    let filtered_sessions: Vec<ManualSession> =
-        sessions.into_iter().filter(|s| s.date == date).collect();
+    let window: Vec<u32> = (0..days)
+        .map(|offset| today - chrono::Duration::days(offset as i64))
+        .map(|date| daily_minutes.get(&date).copied().unwrap_or(0))
+        .collect();
 
-    Ok(filtered_sessions)
+    Ok(consistency_score(&window))
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::async_runtime::block_on(async {
-        tauri::Builder::default()
-            .plugin(tauri_plugin_opener::init())
-            .plugin(tauri_plugin_global_shortcut::Builder::new().build())
-            .plugin(tauri_plugin_dialog::init())
-            .plugin(tauri_plugin_notification::init())
-            .plugin(tauri_plugin_autostart::init(
+// Load of a single focus session. Grows like the minutes for short sessions
+// but logarithmically for long ones: 25 min -> ~17.3, 50 min -> ~27.5,
+// 120 min -> ~43.9.
+fn session_load(minutes: f64) -> f64 {
+    const REFERENCE_MINUTES: f64 = 25.0;
+    REFERENCE_MINUTES * (minutes.max(0.0) / REFERENCE_MINUTES).ln_1p()
+}
+
+#[tauri::command]
+async fn get_focus_load(days: u32, app: AppHandle) -> Result<f64, String> {
+    let manual_sessions = load_manual_sessions(app).await?;
+    let today = chrono::Local::now().date_naive();
+    let in_window = |date: &str| {
+        parse_session_date(date)
+            .map(|date| within_last_days(date, today, days))
+            .unwrap_or(false)
+    };
+
+    // Timer sessions are saved as manual sessions too, so these cover both
+    Ok(manual_sessions
+        .iter()
+        .filter(|s| !is_break_session_type(&s.session_type) && in_window(&s.date))
+        .map(|s| session_load(s.duration as f64))
+        .sum())
+}
+
+// Manual-session minutes per session type over the last `days` days, largest
+// first. Any type found in the data gets a slice, custom ones included.
+#[tauri::command]
+async fn get_type_breakdown(days: u32, app: AppHandle) -> Result<Vec<TypeSlice>, String> {
+    let manual_sessions = load_manual_sessions(app).await?;
+    let today = chrono::Local::now().date_naive();
+
+    let mut minutes: BTreeMap<&str, u32> = BTreeMap::new();
+    for session in &manual_sessions {
+        let Some(date) = parse_session_date(&session.date) else {
+            continue;
+        };
+        if within_last_days(date, today, days) {
+            *minutes.entry(session.session_type.as_str()).or_default() += session.duration;
+        }
+    }
+
+    let total: u32 = minutes.values().sum();
+    if total == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut slices: Vec<TypeSlice> = minutes
+        .into_iter()
+        .map(|(session_type, minutes)| TypeSlice {
+            session_type: session_type.to_string(),
+            minutes,
+            percent: (minutes as f64 / total as f64 * 100.0).round() as u32,
+        })
+        .collect();
+    slices.sort_by(|a, b| b.minutes.cmp(&a.minutes));
+
+    // Rounding can leave the sum at 99 or 101; the largest slice absorbs the difference
+    let rounded_sum: u32 = slices.iter().map(|s| s.percent).sum();
+    slices[0].percent = (slices[0].percent + 100).saturating_sub(rounded_sum);
+
+    Ok(slices)
+}
+
+// Start and end of a manual session; an end before the start crosses midnight
+fn manual_session_span(
+    session: &ManualSession,
+) -> Option<(chrono::NaiveDateTime, chrono::NaiveDateTime)> {
+    let date = parse_session_date(&session.date)?;
+    let parse_time = |time: &str| chrono::NaiveTime::parse_from_str(time, "%H:%M").ok();
+    let start = date.and_time(parse_time(&session.start_time)?);
+    let mut end = date.and_time(parse_time(&session.end_time)?);
+    if end < start {
+        end += chrono::Duration::days(1);
+    }
+    Some((start, end))
+}
+
+// Average idle time between consecutive focus sessions on the same day. Each
+// day's gaps are averaged first so busy days don't dominate; gaps only exist
+// within a day, so the night between two days never counts, and overlapping
+// sessions add no gap.
+#[tauri::command]
+async fn get_avg_session_gap(days: u32, app: AppHandle) -> Result<AvgGap, String> {
+    let manual_sessions = load_manual_sessions(app).await?;
+    let today = chrono::Local::now().date_naive();
+
+    let mut spans_by_day: BTreeMap<
+        chrono::NaiveDate,
+        Vec<(chrono::NaiveDateTime, chrono::NaiveDateTime)>,
+    > = BTreeMap::new();
+    for (start, end) in manual_sessions
+        .iter()
+        .filter(|s| s.session_type == "focus")
+        .filter_map(manual_session_span)
+        .filter(|(start, _)| within_last_days(start.date(), today, days))
+    {
+        spans_by_day
+            .entry(start.date())
+            .or_default()
+            .push((start, end));
+    }
+
+    let mut daily_averages = Vec::new();
+    let mut gaps_counted = 0;
+    for spans in spans_by_day.values_mut() {
+        spans.sort();
+        let gaps: Vec<f64> = spans
+            .windows(2)
+            .map(|pair| (pair[1].0 - pair[0].1).num_minutes())
+            .filter(|&minutes| minutes > 0)
+            .map(|minutes| minutes as f64)
+            .collect();
+        if !gaps.is_empty() {
+            gaps_counted += gaps.len() as u32;
+            daily_averages.push(gaps.iter().sum::<f64>() / gaps.len() as f64);
+        }
+    }
+
+    Ok(AvgGap {
+        avg_gap_minutes: if daily_averages.is_empty() {
+            0.0
+        } else {
+            daily_averages.iter().sum::<f64>() / daily_averages.len() as f64
+        },
+        days_counted: daily_averages.len() as u32,
+        gaps_counted,
+    })
+}
+
+// Focus sessions per hour of day they ended in over the last `days` days,
+// index 0 being midnight. Sessions count towards the day they were logged on,
+// even when they run past midnight.
+#[tauri::command]
+async fn get_completion_hour_distribution(days: u32, app: AppHandle) -> Result<[u32; 24], String> {
+    let manual_sessions = load_manual_sessions(app).await?;
+    let today = chrono::Local::now().date_naive();
+
+    let mut distribution = [0u32; 24];
+    for (_, end) in manual_sessions
+        .iter()
+        .filter(|s| !is_break_session_type(&s.session_type))
+        .filter_map(manual_session_span)
+        .filter(|(start, _)| within_last_days(start.date(), today, days))
+    {
+        distribution[end.hour() as usize] += 1;
+    }
+
+    Ok(distribution)
+}
+
+// Seconds since the most recent session ended, None if none was ever logged.
+// Timer history only keeps daily totals, so this reads the manual-session
+// timeline (which the timer's session bundles add to); the end time falls back
+// to created_at when the times can't be parsed.
+#[tauri::command]
+async fn time_since_last_session(app: AppHandle) -> Result<Option<u64>, String> {
+    let manual_sessions = load_manual_sessions(app).await?;
+
+    let last_end = manual_sessions
+        .iter()
+        .filter_map(|session| {
+            manual_session_span(session)
+                .and_then(|(_, end)| end.and_local_timezone(chrono::Local).earliest())
+                .map(|end| end.fixed_offset())
+                .or_else(|| chrono::DateTime::parse_from_rfc3339(&session.created_at).ok())
+        })
+        .max();
+
+    // A session ending in the future (e.g. logged ahead) counts as just now
+    Ok(last_end.map(|end| {
+        (chrono::Local::now().fixed_offset() - end)
+            .num_seconds()
+            .max(0) as u64
+    }))
+}
+
+// A break counts as taken after a focus session when it starts no earlier than
+// the focus end and at most this many minutes after it
+const BREAK_FOLLOW_WINDOW_MINUTES: i64 = 10;
+
+// Fraction (0-1) of focus sessions in the last `days` days that were followed
+// by a break, based on the manual-session timeline. 0 when there was no focus.
+#[tauri::command]
+async fn get_break_adherence(days: u32, app: AppHandle) -> Result<f64, String> {
+    let manual_sessions = load_manual_sessions(app).await?;
+    Ok(break_adherence(
+        &manual_sessions,
+        chrono::Local::now().date_naive(),
+        days,
+    ))
+}
+
+fn break_adherence(manual_sessions: &[ManualSession], today: chrono::NaiveDate, days: u32) -> f64 {
+    let break_starts: Vec<chrono::NaiveDateTime> = manual_sessions
+        .iter()
+        .filter(|s| is_break_session_type(&s.session_type))
+        .filter_map(|s| manual_session_span(s).map(|(start, _)| start))
+        .collect();
+
+    let focus_ends: Vec<chrono::NaiveDateTime> = manual_sessions
+        .iter()
+        .filter(|s| s.session_type == "focus")
+        .filter_map(manual_session_span)
+        .filter(|(start, _)| within_last_days(start.date(), today, days))
+        .map(|(_, end)| end)
+        .collect();
+
+    if focus_ends.is_empty() {
+        return 0.0;
+    }
+
+    let window = chrono::Duration::minutes(BREAK_FOLLOW_WINDOW_MINUTES);
+    let followed = focus_ends
+        .iter()
+        .filter(|&&end| {
+            break_starts
+                .iter()
+                .any(|&start| start >= end && start - end <= window)
+        })
+        .count();
+
+    followed as f64 / focus_ends.len() as f64
+}
+
+// 0-100 score for a day: 40% pomodoros against the daily session target, 40%
+// focus minutes against the daily goal and 20% break adherence. Each part is
+// capped at its target, so overworking can't make up for skipped breaks. A
+// zero target or goal counts as met.
+fn focus_score(
+    pomodoros: u32,
+    pomodoro_target: u32,
+    focus_minutes: u32,
+    goal_minutes: u32,
+    break_adherence: f64,
+) -> f64 {
+    const POMODORO_WEIGHT: f64 = 40.0;
+    const MINUTES_WEIGHT: f64 = 40.0;
+    const ADHERENCE_WEIGHT: f64 = 20.0;
+
+    let progress = |done: u32, target: u32| {
+        if target == 0 {
+            1.0
+        } else {
+            (done as f64 / target as f64).min(1.0)
+        }
+    };
+
+    POMODORO_WEIGHT * progress(pomodoros, pomodoro_target)
+        + MINUTES_WEIGHT * progress(focus_minutes, goal_minutes)
+        + ADHERENCE_WEIGHT * break_adherence.clamp(0.0, 1.0)
+}
+
+fn focus_grade(score: f64) -> &'static str {
+    match score {
+        s if s >= 90.0 => "A",
+        s if s >= 80.0 => "B",
+        s if s >= 70.0 => "C",
+        s if s >= 60.0 => "D",
+        _ => "F",
+    }
+}
+
+#[tauri::command]
+async fn get_focus_score_today(app: AppHandle) -> Result<FocusScore, String> {
+    let settings = load_settings(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app).await?;
+
+    let today = chrono::Local::now().date_naive();
+    let today_str = format_session_date(today);
+
+    // Finished timer sessions are saved as manual focus sessions, so this counts them too
+    let completed_pomodoros = manual_sessions
+        .iter()
+        .filter(|s| s.session_type == "focus" && s.date == today_str)
+        .count() as u32;
+    let focus_minutes = daily_focus_minutes(&manual_sessions)
+        .get(&today)
+        .copied()
+        .unwrap_or(0);
+    let break_adherence = break_adherence(&manual_sessions, today, 1);
+
+    let score = focus_score(
+        completed_pomodoros,
+        settings.timer.total_sessions,
+        focus_minutes,
+        settings.timer.daily_goal_minutes(),
+        break_adherence,
+    )
+    .round();
+
+    Ok(FocusScore {
+        score: score as u32,
+        grade: focus_grade(score).to_string(),
+        completed_pomodoros,
+        focus_minutes,
+        break_adherence,
+    })
+}
+
+#[tauri::command]
+async fn get_trend(app: AppHandle) -> Result<Trend, String> {
+    let daily_minutes = load_daily_focus_minutes(&app).await?;
+    let today = chrono::Local::now().date_naive();
+    let previous_end = today - chrono::Duration::days(7);
+
+    let sum_window = |end: chrono::NaiveDate| -> u32 {
+        daily_minutes
+            .iter()
+            .filter(|(date, _)| within_last_days(**date, end, 7))
+            .map(|(_, minutes)| minutes)
+            .sum()
+    };
+    let current = sum_window(today);
+    let previous = sum_window(previous_end);
+
+    let delta = current as i64 - previous as i64;
+    let percent_change = if previous > 0 {
+        Some(delta as f64 / previous as f64 * 100.0)
+    } else {
+        None
+    };
+    let direction = match delta.cmp(&0) {
+        std::cmp::Ordering::Greater => "up",
+        std::cmp::Ordering::Less => "down",
+        std::cmp::Ordering::Equal => "flat",
+    };
+
+    Ok(Trend {
+        current,
+        previous,
+        delta,
+        percent_change,
+        direction: direction.to_string(),
+    })
+}
+
+#[tauri::command]
+async fn get_avg_session_length(
+    days: u32,
+    types: Option<Vec<String>>,
+    app: AppHandle,
+) -> Result<AvgSessionLength, String> {
+    let types = types.unwrap_or_default();
+    let week_start = load_settings(app.clone()).await?.timer.week_start();
+    let manual_sessions = load_manual_sessions(app).await?;
+    let today = chrono::Local::now().date_naive();
+
+    let mut total_minutes: u64 = 0;
+    let mut session_count: u32 = 0;
+    // week start -> (minutes, sessions)
+    let mut per_week: BTreeMap<chrono::NaiveDate, (u64, u32)> = BTreeMap::new();
+
+    for session in manual_sessions
+        .iter()
+        .filter(|s| !is_break_session_type(&s.session_type))
+        .filter(|s| matches_session_types(&types, &s.session_type))
+    {
+        let Some(date) = parse_session_date(&session.date) else {
+            continue;
+        };
+        if !within_last_days(date, today, days) {
+            continue;
+        }
+
+        total_minutes += session.duration as u64;
+        session_count += 1;
+
+        let week = per_week.entry(start_of_week(date, week_start)).or_default();
+        week.0 += session.duration as u64;
+        week.1 += 1;
+    }
+
+    let average = |minutes: u64, count: u32| {
+        if count > 0 {
+            minutes as f64 / count as f64
+        } else {
+            0.0
+        }
+    };
+
+    Ok(AvgSessionLength {
+        average_minutes: average(total_minutes, session_count),
+        session_count,
+        weekly: per_week
+            .into_iter()
+            .map(|(week_start, (minutes, count))| WeeklySessionLength {
+                week_start: format_session_date(week_start),
+                average_minutes: average(minutes, count),
+                session_count: count,
+            })
+            .collect(),
+    })
+}
+
+// Focus sessions left before the next long break. Returns 0 when a long break
+// is due right now (today's count just reached a multiple of the interval).
+#[tauri::command]
+async fn sessions_until_long_break(app: AppHandle) -> Result<u32, String> {
+    let interval = load_settings(app.clone())
+        .await?
+        .timer
+        .long_break_interval();
+    let completed = load_session_data(app)
+        .await?
+        .map(|s| s.completed_pomodoros)
+        .unwrap_or(0);
+
+    if completed > 0 && completed % interval == 0 {
+        Ok(0)
+    } else {
+        Ok(interval - completed % interval)
+    }
+}
+
+#[tauri::command]
+async fn update_tray_icon(
+    app: AppHandle,
+    timer_text: String,
+    is_running: bool,
+    session_mode: String,
+    current_session: Option<u32>,
+    total_sessions: Option<u32>,
+    mode_icon: Option<String>,
+) -> Result<(), String> {
+    // Checked here rather than in the main-thread closure below: that closure
+    // only runs after this command has returned, so its errors can't reach the
+    // frontend
+    if app.tray_by_id("main").is_none() {
+        return Err(TRAY_NOT_AVAILABLE.to_string());
+    }
+
+    // Fall back to the progress stored by set_session_progress for any count
+    // the frontend leaves out
+    let (stored_current, stored_total) = *SESSION_PROGRESS.lock().unwrap();
+    let current_session = current_session.unwrap_or(stored_current);
+    let total_sessions = total_sessions.unwrap_or(stored_total);
+
+    let (title_format, mode_icons) = TRAY_DISPLAY
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| (default_tray_title_format(), ModeIcons::default()));
+
+    // A custom icon replaces the built-in glyph for its mode, but not the pause
+    // or overtime glyphs the frontend sends instead. Images go in the icon slot
+    // and leave the title glyph empty.
+    let shows_default_icon = mode_icon
+        .as_deref()
+        .map_or(true, |icon| icon == default_mode_icon(&session_mode));
+    let (mode_icon, tray_image) = match mode_icons.for_mode(&session_mode) {
+        Some(custom) if shows_default_icon && is_icon_image_path(custom) => {
+            (Some(String::new()), Some(custom.to_string()))
+        }
+        Some(custom) if shows_default_icon => (Some(custom.to_string()), None),
+        _ => (mode_icon, None),
+    };
+
+    // Render up front so a bad title format is reported to the caller
+    let icon = mode_icon.unwrap_or_else(|| default_mode_icon(&session_mode).to_string());
+    let values = HashMap::from([
+        ("icon", icon),
+        ("time", timer_text),
+        ("mode", session_mode.clone()),
+        ("session", current_session.to_string()),
+        ("total", total_sessions.to_string()),
+    ]);
+    let title = render_tray_title(&title_format, &values)?;
+
+    let status = if is_running { "Running" } else { "Paused" };
+    let tooltip = if session_mode == "focus" {
+        format!(
+            "Presto - Session {}/{} ({})",
+            current_session, total_sessions, status
+        )
+    } else {
+        format!(
+            "Presto - {} ({})",
+            if session_mode == "longBreak" {
+                "Long Break"
+            } else {
+                "Short Break"
+            },
+            status
+        )
+    };
+
+    // Clone the app handle to move into the closure
+    let app_clone = app.clone();
+
+    // Move the operation to the main thread using Tauri's app handle
+    // This ensures macOS tray operations run on the main thread
+    app.run_on_main_thread(move || {
+        let result = (|| -> Result<(), String> {
+            if let Some(tray) = app_clone.tray_by_id("main") {
+                apply_tray_mode_image(&tray, tray_image.as_deref())?;
+                tray.set_title(Some(title))
+                    .map_err(|e| format!("Failed to set title: {}", e))?;
+                tray.set_tooltip(Some(tooltip))
+                    .map_err(|e| format!("Failed to set tooltip: {}", e))?;
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            log::warn!("Failed to update tray icon: {}", e);
+        }
+    })
+    .map_err(|e| format!("Failed to run on main thread: {}", e))
+}
+
+#[tauri::command]
+fn set_session_progress(current: u32, total: u32) {
+    *SESSION_PROGRESS.lock().unwrap() = (current, total);
+}
+
+// Using simple text-based icons for better cross-platform support
+fn default_mode_icon(mode: &str) -> &'static str {
+    match mode {
+        "focus" => "◉",     // Focus indicator (filled circle)
+        "break" => "☼",     // Break indicator (sun - daytime rest)
+        "longBreak" => "☾", // Long break indicator (moon - night rest)
+        _ => "∞",           // Timer fallback (infinity)
+    }
+}
+
+fn is_icon_image_path(icon: &str) -> bool {
+    std::path::Path::new(icon)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("png") || ext.eq_ignore_ascii_case("ico"))
+        .unwrap_or(false)
+}
+
+// Show a custom mode image as the tray icon, or go back to the plain title
+fn apply_tray_mode_image(tray: &tauri::tray::TrayIcon, path: Option<&str>) -> Result<(), String> {
+    let mut applied = TRAY_MODE_IMAGE.lock().unwrap();
+    if applied.as_deref() == path {
+        return Ok(());
+    }
+
+    match path {
+        Some(path) => {
+            let image = tauri::image::Image::from_path(path)
+                .map_err(|e| format!("Failed to load tray icon {}: {}", path, e))?;
+            tray.set_icon(Some(image))
+                .map_err(|e| format!("Failed to set tray icon: {}", e))?;
+            #[cfg(target_os = "macos")]
+            {
+                let _ = tray.set_icon_as_template(false);
+            }
+        }
+        None => {
+            tray.set_icon(None)
+                .map_err(|e| format!("Failed to clear tray icon: {}", e))?;
+        }
+    }
+
+    *applied = path.map(str::to_string);
+    Ok(())
+}
+
+// Empty = back to the default glyph; otherwise an existing image or a visible glyph
+fn parse_mode_icon(mode: &str, icon: String) -> Result<Option<String>, String> {
+    if icon.is_empty() {
+        return Ok(None);
+    }
+    if is_icon_image_path(&icon) {
+        if !std::path::Path::new(&icon).is_file() {
+            return Err(format!("Icon image for {} not found: {}", mode, icon));
+        }
+        return Ok(Some(icon));
+    }
+    if icon.trim().is_empty() {
+        return Err(format!("Icon for {} is blank", mode));
+    }
+    Ok(Some(icon.trim().to_string()))
+}
+
+#[tauri::command]
+async fn set_mode_icons(
+    focus: String,
+    break_: String,
+    long_break: String,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mode_icons = ModeIcons {
+        focus: parse_mode_icon("focus", focus)?,
+        break_: parse_mode_icon("break", break_)?,
+        long_break: parse_mode_icon("long break", long_break)?,
+    };
+
+    let mut settings = load_settings(app.clone()).await?;
+    settings.mode_icons = mode_icons;
+    save_settings(settings, app).await
+}
+
+// Solid red dot used as the tray "attention" icon
+fn attention_icon() -> tauri::image::Image<'static> {
+    const SIZE: u32 = 32;
+    let center = (SIZE as f32 - 1.0) / 2.0;
+    let radius = SIZE as f32 / 2.0 - 2.0;
+
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let distance = ((x as f32 - center).powi(2) + (y as f32 - center).powi(2)).sqrt();
+            let alpha = if distance <= radius { 255 } else { 0 };
+            rgba.extend_from_slice(&[0xFF, 0x3B, 0x30, alpha]);
+        }
+    }
+
+    tauri::image::Image::new_owned(rgba, SIZE, SIZE)
+}
+
+fn apply_tray_attention(app: &AppHandle, on: bool) -> Result<(), String> {
+    let app_clone = app.clone();
+    app.run_on_main_thread(move || {
+        if let Some(tray) = app_clone.tray_by_id("main") {
+            if on {
+                let _ = tray.set_icon(Some(attention_icon()));
+                // Keep the red tint instead of letting macOS render it as a template
+                #[cfg(target_os = "macos")]
+                {
+                    let _ = tray.set_icon_as_template(false);
+                }
+            } else {
+                let _ = tray.set_icon(None);
+                // This also dropped any custom mode image; reapply it on the next update
+                *TRAY_MODE_IMAGE.lock().unwrap() = None;
+            }
+        }
+    })
+    .map_err(|e| format!("Failed to run on main thread: {}", e))
+}
+
+fn clear_tray_attention(app: &AppHandle) {
+    TRAY_ATTENTION_GENERATION.fetch_add(1, Ordering::SeqCst);
+    let _ = apply_tray_attention(app, false);
+}
+
+#[tauri::command]
+async fn set_tray_attention(
+    on: bool,
+    timeout_secs: Option<u64>,
+    app: AppHandle,
+) -> Result<(), String> {
+    if app.tray_by_id("main").is_none() {
+        return Err(TRAY_NOT_AVAILABLE.to_string());
+    }
+
+    let generation = TRAY_ATTENTION_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    apply_tray_attention(&app, on)?;
+
+    // Auto-clear after the timeout, unless attention changed again in the meantime
+    if let (true, Some(timeout_secs)) = (on, timeout_secs) {
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(timeout_secs));
+            if TRAY_ATTENTION_GENERATION.load(Ordering::SeqCst) == generation {
+                let _ = apply_tray_attention(&app, false);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+// Called by the frontend timer when a break ends. With auto_start_focus on, ticks
+// "auto-start-countdown" (seconds left) once per second for auto_start_delay_secs,
+// then emits "timer-start". Returns false if auto-start is disabled.
+#[tauri::command]
+async fn schedule_auto_start(app: AppHandle) -> Result<bool, String> {
+    let notifications = load_settings(app.clone()).await?.notifications;
+    if !notifications.auto_start_focus {
+        return Ok(false);
+    }
+
+    let generation = AUTO_START_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let delay_secs = notifications.auto_start_delay_secs;
+
+    thread::spawn(move || {
+        for remaining in (1..=delay_secs).rev() {
+            if AUTO_START_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let _ = app.emit("auto-start-countdown", remaining);
+            thread::sleep(Duration::from_secs(1));
+        }
+
+        if AUTO_START_GENERATION.load(Ordering::SeqCst) == generation {
+            let _ = app.emit("timer-start", "focus");
+        }
+    });
+
+    Ok(true)
+}
+
+#[tauri::command]
+async fn cancel_auto_start(app: AppHandle) -> Result<(), String> {
+    AUTO_START_GENERATION.fetch_add(1, Ordering::SeqCst);
+    let _ = app.emit("auto-start-cancelled", ());
+    Ok(())
+}
+
+// Center the window on the preferred monitor if it's connected. If the window
+// ended up off every connected display (e.g. its monitor was unplugged), fall
+// back to the primary monitor.
+fn position_window_on_preferred_monitor(window: &tauri::WebviewWindow, preferred: Option<&str>) {
+    let Ok(monitors) = window.available_monitors() else {
+        return;
+    };
+    let current = window.current_monitor().ok().flatten();
+
+    let preferred_monitor = preferred.and_then(|name| {
+        monitors
+            .iter()
+            .find(|m| m.name().map(String::as_str) == Some(name))
+            .cloned()
+    });
+    let target = match (preferred_monitor, &current) {
+        (Some(monitor), _) => monitor,
+        (None, None) => match window.primary_monitor() {
+            Ok(Some(primary)) => primary,
+            _ => return,
+        },
+        (None, Some(_)) => return,
+    };
+
+    // Already there, don't move the window around
+    if current.as_ref().and_then(|m| m.name()) == target.name() {
+        return;
+    }
+
+    let Ok(window_size) = window.outer_size() else {
+        return;
+    };
+    let position = target.position();
+    let size = target.size();
+    let x = position.x + (size.width as i32 - window_size.width as i32) / 2;
+    let y = position.y + (size.height as i32 - window_size.height as i32) / 2;
+    let _ = window.set_position(tauri::PhysicalPosition::new(x, y));
+}
+
+// The frontend enables this when a break starts and disables it when focus
+// starts. Enabling is a no-op unless break_always_on_top is set.
+#[tauri::command]
+async fn set_always_on_top(enabled: bool, app: AppHandle) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+
+    if enabled && !load_settings(app.clone()).await?.break_always_on_top {
+        return Ok(());
+    }
+
+    window
+        .set_always_on_top(enabled)
+        .map_err(|e| format!("Failed to set always on top: {}", e))
+}
+
+// Shrink the main window to a small fixed-size timer, or restore it. The
+// normal size is saved to settings before shrinking so it survives a restart
+// in mini mode.
+#[tauri::command]
+async fn set_mini_mode(enabled: bool, app: AppHandle) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+    let mut settings = load_settings(app.clone()).await?;
+
+    if enabled {
+        // Enabling twice must not record the mini size as the normal one
+        if !MINI_MODE.swap(true, Ordering::SeqCst) {
+            let scale_factor = window
+                .scale_factor()
+                .map_err(|e| format!("Failed to get scale factor: {}", e))?;
+            let size = window
+                .inner_size()
+                .map_err(|e| format!("Failed to get window size: {}", e))?
+                .to_logical::<f64>(scale_factor);
+            settings.normal_window_size = Some(WindowSize {
+                width: size.width,
+                height: size.height,
+            });
+            save_settings(settings.clone(), app.clone()).await?;
+        }
+
+        window
+            .set_resizable(false)
+            .map_err(|e| format!("Failed to set resizable: {}", e))?;
+        window
+            .set_size(tauri::LogicalSize::new(
+                MINI_WINDOW_SIZE.width,
+                MINI_WINDOW_SIZE.height,
+            ))
+            .map_err(|e| format!("Failed to resize window: {}", e))?;
+        if settings.mini_mode_frameless {
+            window
+                .set_decorations(false)
+                .map_err(|e| format!("Failed to hide decorations: {}", e))?;
+        }
+        if settings.mini_mode_always_on_top {
+            window
+                .set_always_on_top(true)
+                .map_err(|e| format!("Failed to set always on top: {}", e))?;
+        }
+    } else {
+        MINI_MODE.store(false, Ordering::SeqCst);
+
+        let size = settings.normal_window_size.unwrap_or(DEFAULT_WINDOW_SIZE);
+        window
+            .set_decorations(true)
+            .map_err(|e| format!("Failed to restore decorations: {}", e))?;
+        window
+            .set_always_on_top(false)
+            .map_err(|e| format!("Failed to clear always on top: {}", e))?;
+        window
+            .set_resizable(true)
+            .map_err(|e| format!("Failed to set resizable: {}", e))?;
+        window
+            .set_size(tauri::LogicalSize::new(size.width, size.height))
+            .map_err(|e| format!("Failed to resize window: {}", e))?;
+    }
+
+    app.emit("mini-mode", enabled)
+        .map_err(|e| format!("Failed to emit mini mode change: {}", e))
+}
+
+fn theme_name(theme: tauri::Theme) -> &'static str {
+    match theme {
+        tauri::Theme::Dark => "dark",
+        _ => "light",
+    }
+}
+
+// The OS appearance, regardless of the theme setting
+#[tauri::command]
+async fn get_system_theme(app: AppHandle) -> Result<String, String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+    let theme = window
+        .theme()
+        .map_err(|e| format!("Failed to get system theme: {}", e))?;
+    Ok(theme_name(theme).to_string())
+}
+
+#[tauri::command]
+async fn is_window_focused(app: AppHandle) -> Result<bool, String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+    window
+        .is_focused()
+        .map_err(|e| format!("Failed to check window focus: {}", e))
+}
+
+#[tauri::command]
+async fn list_monitors(app: AppHandle) -> Result<Vec<String>, String> {
+    let monitors = app
+        .available_monitors()
+        .map_err(|e| format!("Failed to list monitors: {}", e))?;
+    Ok(monitors.iter().filter_map(|m| m.name().cloned()).collect())
+}
+
+// The single-instance plugin exits any later launch before it can serve
+// commands, so whichever process answers this is the primary one. Kept for the
+// frontend to confirm it isn't running alongside another copy.
+#[tauri::command]
+fn is_primary_instance() -> bool {
+    true
+}
+
+#[tauri::command]
+async fn show_window(app: AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("main") {
+        // Check if hide_icon_on_close is enabled to restore dock visibility
+        match load_settings(app.clone()).await {
+            Ok(settings) => {
+                position_window_on_preferred_monitor(
+                    &window,
+                    settings.preferred_monitor.as_deref(),
+                );
+
+                if settings.hide_icon_on_close && !MENUBAR_ONLY.load(Ordering::SeqCst) {
+                    // Restore dock visibility when showing window
+                    #[cfg(target_os = "macos")]
+                    {
+                        let _ = set_dock_visibility(app.clone(), true).await;
+                    }
+
+                    // Restore the taskbar entry on Windows/Linux
+                    #[cfg(any(target_os = "windows", target_os = "linux"))]
+                    {
+                        set_taskbar_visibility(&window, true);
+                    }
+                }
+            }
+            Err(_) => {
+                // Ignore error, just proceed with showing window
+            }
+        }
+
+        clear_tray_attention(&app);
+
+        window
+            .show()
+            .map_err(|e| format!("Failed to show window: {}", e))?;
+        window
+            .set_focus()
+            .map_err(|e| format!("Failed to focus window: {}", e))?;
+    }
+    Ok(())
+}
+
+fn validate_week_start_day(day: &str) -> Result<(), String> {
+    if !matches!(day, "monday" | "sunday") {
+        return Err(format!(
+            "Unknown week start day '{}' (expected monday or sunday)",
+            day
+        ));
+    }
+    Ok(())
+}
+
+// Check a timer configuration before it is saved. Impossible values are an
+// error; anything merely unusual comes back as a warning for the settings UI.
+#[tauri::command]
+fn validate_timer_settings(timer: TimerSettings) -> Result<Vec<String>, String> {
+    const MINUTES_PER_DAY: u32 = 24 * 60;
+
+    for (name, minutes) in [
+        ("Focus", timer.focus_duration),
+        ("Break", timer.break_duration),
+        ("Long break", timer.long_break_duration),
+    ] {
+        if minutes == 0 {
+            return Err(format!("{} duration must be at least 1 minute", name));
+        }
+        if minutes > MINUTES_PER_DAY {
+            return Err(format!(
+                "{} duration of {} minutes is longer than a day",
+                name, minutes
+            ));
+        }
+    }
+
+    validate_week_start_day(&timer.week_start_day)?;
+
+    let mut warnings = Vec::new();
+    if timer.break_duration > timer.focus_duration {
+        warnings.push(format!(
+            "Breaks ({} min) are longer than focus sessions ({} min)",
+            timer.break_duration, timer.focus_duration
+        ));
+    }
+    if timer.long_break_duration < timer.break_duration {
+        warnings.push(format!(
+            "Long breaks ({} min) are shorter than regular breaks ({} min)",
+            timer.long_break_duration, timer.break_duration
+        ));
+    }
+    if timer.focus_duration > 180 {
+        warnings.push(format!(
+            "Focus sessions of {} minutes are unusually long",
+            timer.focus_duration
+        ));
+    }
+    if timer.total_sessions == 0 {
+        warnings.push("A daily target of 0 sessions is always met".to_string());
+    }
+    if timer.long_break_interval == 0 {
+        warnings.push("A long break interval of 0 is treated as every session".to_string());
+    } else if timer.total_sessions > 0 && timer.long_break_interval > timer.total_sessions {
+        warnings.push(format!(
+            "A long break every {} sessions is never reached with a target of {}",
+            timer.long_break_interval, timer.total_sessions
+        ));
+    }
+
+    Ok(warnings)
+}
+
+// Minutes to pre-fill when logging a session of this type
+#[tauri::command]
+async fn get_default_duration(session_type: String, app: AppHandle) -> Result<u32, String> {
+    let settings = load_settings(app).await?;
+    Ok(settings.timer.default_duration(&session_type))
+}
+
+#[tauri::command]
+async fn save_settings(settings: AppSettings, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    // Reject tray title formats with unknown placeholders before persisting them
+    render_tray_title(&settings.tray_title_format, &HashMap::new())?;
+
+    validate_week_start_day(&settings.timer.week_start_day)?;
+
+    if !matches!(settings.theme.as_str(), "system" | "light" | "dark") {
+        return Err(format!(
+            "Unknown theme '{}' (expected system, light or dark)",
+            settings.theme
+        ));
+    }
+
+    // Catch a bad export folder now rather than on the first scheduled run
+    if settings.advanced.auto_export_enabled {
+        validate_auto_export_dir(&settings.advanced.auto_export_path)?;
+    }
+
+    if let Some(url) = &settings.advanced.completion_webhook_url {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            // The URL itself may embed a token, so keep it out of the message
+            return Err("Webhook URL must start with http:// or https://".to_string());
+        }
+    }
+
+    // Custom sounds must exist and be in a playable format
+    for sound in [
+        &settings.notifications.sound_focus_end,
+        &settings.notifications.sound_break_end,
+        &settings.notifications.sound_long_break_end,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        validate_sound_file(sound)?;
+    }
+
+    let app_data_dir = get_data_dir(&app)?;
+
+    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let file_path = app_data_dir.join("settings.json");
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    write_file_atomic(&file_path, &json, settings.advanced.durable_writes)
+        .map_err(|e| format!("Failed to write settings file: {}", e))?;
+    cache_runtime_settings(&settings);
+
+    // A running ticker was started with the old settings
+    if TICK_WANTED.load(Ordering::SeqCst) {
+        restart_tick_player(&settings.notifications)?;
+    }
+
+    Ok(())
+}
+
+fn cache_runtime_settings(settings: &AppSettings) {
+    LOCK_PAUSES_TIMER.store(settings.notifications.lock_pauses_timer, Ordering::SeqCst);
+    DEBUG_MODE.store(settings.advanced.debug_mode, Ordering::SeqCst);
+    *TRAY_DISPLAY.lock().unwrap() = Some((
+        settings.tray_title_format.clone(),
+        settings.mode_icons.clone(),
+    ));
+}
+
+// Re-read the cached settings after the data directory or workspace changed
+fn refresh_runtime_settings(app: &AppHandle) {
+    match read_settings(app) {
+        Ok(settings) => cache_runtime_settings(&settings),
+        Err(e) => log::warn!("Failed to refresh cached settings: {}", e),
+    }
+}
+
+#[tauri::command]
+async fn load_settings(app: AppHandle) -> Result<AppSettings, String> {
+    read_settings(&app)
+}
+
+// Synchronous settings read for window and run event handlers, which already run
+// inside the async runtime and so can't block on load_settings
+fn read_settings(app: &AppHandle) -> Result<AppSettings, String> {
+    let app_data_dir = get_data_dir(app)?;
+    let file_path = app_data_dir.join("settings.json");
+
+    if !file_path.exists() {
+        return Ok(AppSettings::default());
+    }
+
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read settings file: {}", e))?;
+    let settings: AppSettings =
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse settings: {}", e))?;
+
+    Ok(settings)
+}
+
+// Toggle the 3-second debug timers without a restart. The timer runs in the
+// frontend, which applies the new durations when it gets "debug-mode-changed".
+// Debug sessions are never reported to analytics (see are_analytics_enabled);
+// the frontend should also keep them out of history, since they would skew
+// every statistic.
+#[tauri::command]
+async fn set_debug_mode(enabled: bool, app: AppHandle) -> Result<(), String> {
+    let mut settings = load_settings(app.clone()).await?;
+    settings.advanced.debug_mode = enabled;
+    save_settings(settings, app.clone()).await?;
+
+    log::info!(
+        "Debug mode {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    app.emit("debug-mode-changed", enabled)
+        .map_err(|e| format!("Failed to emit debug mode change: {}", e))
+}
+
+// Fire the events of a finished session on demand, so integrations built on
+// the event stream can be exercised without waiting out a timer. Only
+// available in debug mode.
+#[tauri::command]
+async fn emit_test_session_complete(mode: String, app: AppHandle) -> Result<(), String> {
+    if !load_settings(app.clone()).await?.advanced.debug_mode {
+        return Err("Test events are only available in debug mode".to_string());
+    }
+    if !matches!(mode.as_str(), "focus" | "break" | "longBreak") {
+        return Err(format!("Unknown session mode '{}'", mode));
+    }
+
+    let payload = serde_json::json!({ "mode": mode, "simulated": true });
+    app.emit("timer-complete", &payload)
+        .map_err(|e| format!("Failed to emit timer-complete: {}", e))?;
+    app.emit(
+        "notification-action",
+        serde_json::json!({ "action": "session-complete", "mode": mode, "simulated": true }),
+    )
+    .map_err(|e| format!("Failed to emit notification-action: {}", e))
+}
+
+// Settings keys that must never leave the machine in diagnostics output
+const SENSITIVE_SETTINGS_KEYS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "oauth_token",
+    "api_key",
+    "data_dir",
+    "completion_webhook_url",
+];
+
+// Recursively blank out sensitive values, wherever they are nested
+fn redact_sensitive_values(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, entry) in map.iter_mut() {
+                if SENSITIVE_SETTINGS_KEYS.contains(&key.as_str()) {
+                    *entry = serde_json::Value::String("[redacted]".to_string());
+                } else {
+                    redact_sensitive_values(entry);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_sensitive_values),
+        _ => {}
+    }
+}
+
+#[tauri::command]
+async fn get_settings_json(redact: bool, app: AppHandle) -> Result<String, String> {
+    let settings = load_settings(app).await?;
+    let mut value = serde_json::to_value(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    if redact {
+        redact_sensitive_values(&mut value);
+    }
+
+    serde_json::to_string_pretty(&value).map_err(|e| format!("Failed to serialize settings: {}", e))
+}
+
+#[tauri::command]
+async fn register_global_shortcuts(
+    app: AppHandle,
+    shortcuts: ShortcutSettings,
+) -> Result<(), String> {
+    // Unregister all existing shortcuts first
+    app.global_shortcut()
+        .unregister_all()
+        .map_err(|e| format!("Failed to unregister shortcuts: {}", e))?;
+
+    // Register start/stop shortcut
+    if let Some(ref shortcut_str) = shortcuts.start_stop {
+        let shortcut: Shortcut = shortcut_str
+            .parse()
+            .map_err(|e| format!("Invalid start/stop shortcut '{}': {}", shortcut_str, e))?;
+
+        let app_handle = app.clone();
+        app.global_shortcut()
+            .on_shortcut(shortcut, move |_app, _shortcut, _event| {
+                if !should_debounce_shortcut("start-stop") {
+                    let _ = app_handle.emit("global-shortcut", "start-stop");
+                }
+            })
+            .map_err(|e| format!("Failed to register start/stop shortcut: {}", e))?;
+    }
+
+    // Register reset shortcut
+    if let Some(ref shortcut_str) = shortcuts.reset {
+        let shortcut: Shortcut = shortcut_str
+            .parse()
+            .map_err(|e| format!("Invalid reset shortcut '{}': {}", shortcut_str, e))?;
+
+        let app_handle = app.clone();
+        app.global_shortcut()
+            .on_shortcut(shortcut, move |_app, _shortcut, _event| {
+                if !should_debounce_shortcut("reset") {
+                    let _ = app_handle.emit("global-shortcut", "reset");
+                }
+            })
+            .map_err(|e| format!("Failed to register reset shortcut: {}", e))?;
+    }
+
+    // Register skip shortcut
+    if let Some(ref shortcut_str) = shortcuts.skip {
+        let shortcut: Shortcut = shortcut_str
+            .parse()
+            .map_err(|e| format!("Invalid skip shortcut '{}': {}", shortcut_str, e))?;
+
+        let app_handle = app.clone();
+        app.global_shortcut()
+            .on_shortcut(shortcut, move |_app, _shortcut, _event| {
+                if !should_debounce_shortcut("skip") {
+                    let _ = app_handle.emit("global-shortcut", "skip");
+                }
+            })
+            .map_err(|e| format!("Failed to register skip shortcut: {}", e))?;
+    }
+
+    // Register extend shortcut
+    if let Some(ref shortcut_str) = shortcuts.extend {
+        let shortcut: Shortcut = shortcut_str
+            .parse()
+            .map_err(|e| format!("Invalid extend shortcut '{}': {}", shortcut_str, e))?;
+
+        let app_handle = app.clone();
+        app.global_shortcut()
+            .on_shortcut(shortcut, move |_app, _shortcut, _event| {
+                if !should_debounce_shortcut("extend") {
+                    let _ = app_handle.emit("global-shortcut", "extend");
+                }
+            })
+            .map_err(|e| format!("Failed to register extend shortcut: {}", e))?;
+    }
+
+    // Emit an event to the frontend to update local shortcuts as well
+    app.emit("shortcuts-updated", &shortcuts)
+        .map_err(|e| format!("Failed to emit shortcuts update: {}", e))?;
+
+    Ok(())
+}
+
+// Add (or, with a negative value, remove) time from the running countdown. The
+// countdown lives in the frontend timer, which clamps the result at zero.
+#[tauri::command]
+async fn extend_timer(seconds: i64, app: AppHandle) -> Result<(), String> {
+    app.emit("timer-extend", seconds)
+        .map_err(|e| format!("Failed to extend timer: {}", e))
+}
+
+#[tauri::command]
+async fn unregister_global_shortcuts(app: AppHandle) -> Result<(), String> {
+    app.global_shortcut()
+        .unregister_all()
+        .map_err(|e| format!("Failed to unregister shortcuts: {}", e))?;
+    Ok(())
+}
+
+// One-shot shortcut capture for onboarding. The global shortcut plugin can only
+// listen for combinations it already knows, so a truly global capture isn't
+// possible; instead the window is brought forward and the settings manager
+// forwards the next keydown combination through the "shortcut-capture-key"
+// event while a capture is in progress. The user's registered shortcuts are
+// left untouched.
+#[tauri::command]
+async fn capture_next_shortcut(timeout_secs: u64, app: AppHandle) -> Result<String, String> {
+    use std::sync::mpsc;
+    use tauri::Listener;
+
+    let (tx, rx) = mpsc::channel::<String>();
+    let listener_id = app.listen("shortcut-capture-key", move |event| {
+        if let Ok(raw) = serde_json::from_str::<String>(event.payload()) {
+            let _ = tx.send(raw);
+        }
+    });
+
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = app.emit("shortcut-capture-started", timeout_secs);
+
+    let timeout = Duration::from_secs(timeout_secs);
+    let capture = tauri::async_runtime::spawn_blocking(move || {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(remaining) {
+                // Normalize through the plugin parser; ignore modifier-only or invalid combos
+                Ok(raw) => match raw.parse::<Shortcut>() {
+                    Ok(shortcut) => return Ok(shortcut.into_string()),
+                    Err(_) => continue,
+                },
+                Err(_) => return Err("Timed out waiting for a shortcut".to_string()),
+            }
+        }
+    })
+    .await
+    .map_err(|e| format!("Failed to capture shortcut: {}", e));
+
+    // Always clean up, whether we captured something or timed out
+    app.unlisten(listener_id);
+    let _ = app.emit("shortcut-capture-ended", ());
+
+    capture?
+}
+
+#[tauri::command]
+async fn reset_all_data(app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let app_data_dir = get_data_dir(&app)?;
+
+    let files_to_delete = vec![
+        "session.json",
+        "tasks.json",
+        "history.json",
+        "settings.json",
+        "manual_sessions.json",
+        "timer_state.json",
+    ];
+
+    for file_name in files_to_delete {
+        let file_path = app_data_dir.join(file_name);
+        if file_path.exists() {
+            fs::remove_file(file_path)
+                .map_err(|e| format!("Failed to delete {}: {}", file_name, e))?;
+        }
+    }
+
+    // Remove yearly history archives as well
+    if let Ok(entries) = fs::read_dir(&app_data_dir) {
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with("history-") && file_name.ends_with(".json") {
+                fs::remove_file(entry.path())
+                    .map_err(|e| format!("Failed to delete {}: {}", file_name, e))?;
+            }
+        }
+    }
+    invalidate_stats_cache();
+    refresh_runtime_settings(&app);
+
+    /*
+    if app_data_dir.exists() {
+        let _ = fs::remove_dir(&app_data_dir);
+    }
+    */
+
+    Ok(())
+}
+
+// Turn an io::Error into a message that tells the user what actually went wrong
+fn describe_io_error(e: &std::io::Error) -> String {
+    use std::io::ErrorKind;
+
+    let reason = match e.kind() {
+        ErrorKind::PermissionDenied => "permission denied",
+        ErrorKind::ReadOnlyFilesystem => "read-only filesystem",
+        ErrorKind::StorageFull => "disk is full",
+        ErrorKind::NotFound => "path not found",
+        ErrorKind::AlreadyExists => "file already exists",
+        _ => "I/O error",
+    };
+    format!("{} ({})", reason, e)
+}
+
+#[tauri::command]
+async fn check_data_dir_writable(app: AppHandle) -> Result<(), String> {
+    let app_data_dir = get_data_dir(&app)?;
+
+    fs::create_dir_all(&app_data_dir).map_err(|e| {
+        format!(
+            "Cannot create data directory {}: {}",
+            app_data_dir.display(),
+            describe_io_error(&e)
+        )
+    })?;
+
+    let probe_path = app_data_dir.join(".write_probe");
+    let probe_content = "presto write probe";
+
+    fs::write(&probe_path, probe_content).map_err(|e| {
+        format!(
+            "Cannot write to data directory {}: {}",
+            app_data_dir.display(),
+            describe_io_error(&e)
+        )
+    })?;
+
+    let read_back = fs::read_to_string(&probe_path).map_err(|e| {
+        format!(
+            "Cannot read back probe file {}: {}",
+            probe_path.display(),
+            describe_io_error(&e)
+        )
+    });
+
+    // Always try to clean up, even if the read failed
+    let removed = fs::remove_file(&probe_path).map_err(|e| {
+        format!(
+            "Cannot delete probe file {}: {}",
+            probe_path.display(),
+            describe_io_error(&e)
+        )
+    });
+
+    if read_back? != probe_content {
+        return Err(format!(
+            "Data directory {} returned different content than was written",
+            app_data_dir.display()
+        ));
+    }
+    removed?;
+
+    Ok(())
+}
+
+// Check a local time against the quiet hours window, which may cross midnight
+fn is_in_quiet_hours(notifications: &NotificationSettings, now: chrono::NaiveTime) -> bool {
+    if !notifications.quiet_hours_enabled {
+        return false;
+    }
+
+    let parse = |time: &str| chrono::NaiveTime::parse_from_str(time, "%H:%M").ok();
+    let (Some(start), Some(end)) = (
+        parse(&notifications.quiet_start),
+        parse(&notifications.quiet_end),
+    ) else {
+        return false;
+    };
+
+    if start <= end {
+        now >= start && now < end
+    } else {
+        // e.g. 22:00 -> 07:00
+        now >= start || now < end
+    }
+}
+
+#[tauri::command]
+async fn in_quiet_hours(app: AppHandle) -> Result<bool, String> {
+    let settings = load_settings(app).await?;
+    Ok(is_in_quiet_hours(
+        &settings.notifications,
+        chrono::Local::now().time(),
+    ))
+}
+
+// A mute deadline that has passed (or can't be parsed) no longer applies
+fn is_notification_muted(notifications: &NotificationSettings) -> bool {
+    notifications
+        .muted_until
+        .as_deref()
+        .and_then(|until| chrono::DateTime::parse_from_rfc3339(until).ok())
+        .map(|until| until > chrono::Local::now())
+        .unwrap_or(false)
+}
+
+#[tauri::command]
+async fn mute_notifications_until(iso_timestamp: String, app: AppHandle) -> Result<(), String> {
+    let until = chrono::DateTime::parse_from_rfc3339(&iso_timestamp)
+        .map_err(|e| format!("Invalid timestamp '{}': {}", iso_timestamp, e))?;
+    if until <= chrono::Local::now() {
+        return Err(format!("Mute deadline {} is in the past", iso_timestamp));
+    }
+
+    let mut settings = load_settings(app.clone()).await?;
+    settings.notifications.muted_until = Some(until.to_rfc3339());
+    save_settings(settings, app).await
+}
+
+#[tauri::command]
+async fn clear_notification_mute(app: AppHandle) -> Result<(), String> {
+    let mut settings = load_settings(app.clone()).await?;
+    settings.notifications.muted_until = None;
+    save_settings(settings, app).await
+}
+
+#[tauri::command]
+async fn is_muted(app: AppHandle) -> Result<bool, String> {
+    let settings = load_settings(app).await?;
+    Ok(is_notification_muted(&settings.notifications))
+}
+
+// Show a session-complete banner unless notifications are off, muted or we're in quiet
+// hours. Returns whether the notification was actually shown; the timer itself
+// completes either way.
+#[tauri::command]
+async fn notify_session_complete(
+    title: String,
+    body: String,
+    app: AppHandle,
+) -> Result<bool, String> {
+    use tauri_plugin_notification::NotificationExt;
+
+    let settings = load_settings(app.clone()).await?;
+    if !settings.notifications.desktop_notifications
+        || is_notification_muted(&settings.notifications)
+        || is_in_quiet_hours(&settings.notifications, chrono::Local::now().time())
+    {
+        return Ok(false);
+    }
+
+    app.notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+        .map_err(|e| format!("Failed to show notification: {}", e))?;
+
+    Ok(true)
+}
+
+const SUPPORTED_SOUND_EXTENSIONS: &[&str] = &["wav", "mp3", "aiff", "aif", "m4a", "ogg", "oga"];
+
+// Make sure a custom sound path points to an existing file in a playable format
+fn validate_sound_file(path: &str) -> Result<(), String> {
+    let path = std::path::Path::new(path);
+    if !path.is_file() {
+        return Err(format!("Sound file not found: {}", path.display()));
+    }
+
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+    if !SUPPORTED_SOUND_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!(
+            "Unsupported sound format '{}' (supported: {})",
+            path.display(),
+            SUPPORTED_SOUND_EXTENSIONS.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+// Built-in system sounds used when no custom sound is configured
+fn default_sound_path(kind: &str) -> &'static str {
+    #[cfg(target_os = "macos")]
+    {
+        match kind {
+            "tick" => "/System/Library/Sounds/Tink.aiff",
+            "focus_end" => "/System/Library/Sounds/Glass.aiff",
+            "long_break_end" => "/System/Library/Sounds/Hero.aiff",
+            _ => "/System/Library/Sounds/Ping.aiff",
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        match kind {
+            "tick" => "C:\\Windows\\Media\\Windows Navigation Start.wav",
+            "focus_end" => "C:\\Windows\\Media\\Windows Notify System Generic.wav",
+            _ => "C:\\Windows\\Media\\Windows Notify.wav",
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        match kind {
+            "tick" => "/usr/share/sounds/freedesktop/stereo/audio-volume-change.oga",
+            "focus_end" => "/usr/share/sounds/freedesktop/stereo/complete.oga",
+            _ => "/usr/share/sounds/freedesktop/stereo/bell.oga",
+        }
+    }
+}
+
+// Play a sound file at full volume with the platform's command-line player,
+// without blocking
+fn spawn_sound_player(path: &str) -> Result<(), String> {
+    use std::process::Command;
+
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("afplay");
+        command.arg(path);
+        command
+    };
+
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let script = format!(
+            "(New-Object Media.SoundPlayer '{}').PlaySync()",
+            path.replace('\'', "''")
+        );
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command", script.as_str()]);
+        command
+    };
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = {
+        let mut command = Command::new("paplay");
+        command.arg(path);
+        command
+    };
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to play sound {}: {}", path, e))?;
+
+    // Reap the player once it finishes so it doesn't linger as a zombie
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    Ok(())
+}
+
+// Start one player process that plays `path` at `volume` (0.0 - 1.0) every
+// `interval_secs` seconds until it is killed, so ticking doesn't spawn a player
+// per tick from the app. It also exits on its own once the app is gone.
+fn spawn_tick_player(
+    path: &str,
+    volume: f32,
+    interval_secs: u32,
+) -> Result<std::process::Child, String> {
+    use std::process::Command;
+
+    let volume = volume.clamp(0.0, 1.0);
+    let interval = interval_secs.max(1);
+
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        // MediaPlayer keeps the sample loaded and, unlike SoundPlayer, has a volume
+        let script = format!(
+            "Add-Type -AssemblyName PresentationCore; \
+             $player = New-Object System.Windows.Media.MediaPlayer; \
+             $player.Open([Uri]'{}'); $player.Volume = {}; \
+             while (Get-Process -Id {} -ErrorAction SilentlyContinue) {{ \
+             $player.Position = [TimeSpan]::Zero; $player.Play(); Start-Sleep -Seconds {} }}",
+            path.replace('\'', "''"),
+            volume,
+            std::process::id(),
+            interval
+        );
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command", script.as_str()]);
+        command
+    };
+
+    // The shell loop stands in for a looping player; each tick plays in the
+    // background so the sleep alone sets the tick rate
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "while kill -0 $PPID 2>/dev/null; do afplay -v \"$1\" \"$2\" & sleep \"$3\"; done",
+            "sh",
+            &volume.to_string(),
+            path,
+            &interval.to_string(),
+        ]);
+        command
+    };
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = {
+        // paplay volume is linear, 65536 = 100%
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "while kill -0 $PPID 2>/dev/null; do paplay --volume=\"$1\" \"$2\" & sleep \"$3\"; done",
+            "sh",
+            &((volume * 65536.0) as u32).to_string(),
+            path,
+            &interval.to_string(),
+        ]);
+        command
+    };
+
+    command
+        .spawn()
+        .map_err(|e| format!("Failed to start tick sound: {}", e))
+}
+
+// Replace the running tick player with one for the given settings, or just
+// stop it when ticking is off or notifications are muted
+fn restart_tick_player(notifications: &NotificationSettings) -> Result<(), String> {
+    let mut player = TICK_PLAYER.lock().unwrap();
+    if let Some(mut child) = player.take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    if !notifications.sound_notifications
+        || !notifications.tick_sound_enabled
+        || is_notification_muted(notifications)
+    {
+        return Ok(());
+    }
+
+    *player = Some(spawn_tick_player(
+        default_sound_path("tick"),
+        notifications.tick_volume,
+        notifications.tick_interval_secs,
+    )?);
+    Ok(())
+}
+
+// kind: "focus_end", "break_end" or "long_break_end"
+#[tauri::command]
+async fn play_sound(kind: String, app: AppHandle) -> Result<(), String> {
+    let settings = load_settings(app).await?;
+    let notifications = &settings.notifications;
+    if !notifications.sound_notifications || is_notification_muted(notifications) {
+        return Ok(());
+    }
+
+    let custom_sound = match kind.as_str() {
+        "focus_end" => notifications.sound_focus_end.as_deref(),
+        "break_end" => notifications.sound_break_end.as_deref(),
+        "long_break_end" => notifications.sound_long_break_end.as_deref(),
+        other => return Err(format!("Unknown sound kind '{}'", other)),
+    };
+
+    // Fall back to the default if the custom file went missing since it was saved
+    let path = custom_sound
+        .filter(|path| validate_sound_file(path).is_ok())
+        .unwrap_or_else(|| default_sound_path(&kind));
+
+    spawn_sound_player(path)
+}
+
+// Start the ticking clock for a focus session. The frontend calls this when
+// focus starts or resumes and stop_tick_sound on pause, stop or session end.
+// Settings are read once here; save_settings restarts a running ticker, so
+// toggling sounds off still takes effect at once.
+#[tauri::command]
+async fn start_tick_sound(app: AppHandle) -> Result<(), String> {
+    let settings = load_settings(app).await?;
+    TICK_WANTED.store(true, Ordering::SeqCst);
+    restart_tick_player(&settings.notifications)
+}
+
+#[tauri::command]
+async fn stop_tick_sound() -> Result<(), String> {
+    TICK_WANTED.store(false, Ordering::SeqCst);
+    if let Some(mut child) = TICK_PLAYER.lock().unwrap().take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    Ok(())
+}
+
+// Whether `normalized` (a store re-serialized after parsing) has object keys
+// that the file's `raw` JSON lacks. Only keys are compared: values can differ
+// harmlessly, e.g. an f32 like 0.3 re-serializes as 0.30000001192092896.
+// Keys that come back as null weren't filled with anything.
+fn has_missing_keys(normalized: &serde_json::Value, raw: &serde_json::Value) -> bool {
+    match (normalized, raw) {
+        (serde_json::Value::Object(normalized), serde_json::Value::Object(raw)) => {
+            normalized.iter().any(|(key, value)| match raw.get(key) {
+                Some(raw_value) => has_missing_keys(value, raw_value),
+                None => !value.is_null(),
+            })
+        }
+        (serde_json::Value::Array(normalized), serde_json::Value::Array(raw)) => normalized
+            .iter()
+            .zip(raw)
+            .any(|(value, raw_value)| has_missing_keys(value, raw_value)),
+        _ => false,
+    }
+}
+
+// Try to load one store. "migrated" means it parsed, but only after serde
+// filled in defaults for fields the file doesn't have yet.
+fn check_store<T: serde::de::DeserializeOwned + Serialize>(
+    app_data_dir: &std::path::Path,
+    file_name: &str,
+) -> FileHealth {
+    let health = |status: &str, detail: Option<String>| FileHealth {
+        file: file_name.to_string(),
+        status: status.to_string(),
+        detail,
+    };
+
+    let path = app_data_dir.join(file_name);
+    if !path.exists() {
+        return health("missing", None);
+    }
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => return health("corrupt", Some(describe_io_error(&e))),
+    };
+    let raw: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(raw) => raw,
+        Err(e) => return health("corrupt", Some(format!("Invalid JSON: {}", e))),
+    };
+    let parsed: T = match serde_json::from_value(raw.clone()) {
+        Ok(parsed) => parsed,
+        Err(e) => return health("corrupt", Some(format!("Unexpected format: {}", e))),
+    };
+
+    match serde_json::to_value(&parsed) {
+        Ok(normalized) if has_missing_keys(&normalized, &raw) => health(
+            "migrated",
+            Some("Missing fields were filled with defaults".to_string()),
+        ),
+        _ => health("ok", None),
+    }
+}
+
+#[tauri::command]
+async fn health_check(app: AppHandle) -> Result<HealthReport, String> {
+    let app_data_dir = get_data_dir(&app)?;
+
+    let files = vec![
+        check_store::<AppSettings>(&app_data_dir, "settings.json"),
+        check_store::<PomodoroSession>(&app_data_dir, "session.json"),
+        check_store::<Vec<Task>>(&app_data_dir, "tasks.json"),
+        check_store::<Vec<PomodoroSession>>(&app_data_dir, "history.json"),
+        check_store::<Vec<ManualSession>>(&app_data_dir, "manual_sessions.json"),
+        check_store::<Vec<Tag>>(&app_data_dir, "tags.json"),
+        check_store::<Vec<SessionTag>>(&app_data_dir, "session_tags.json"),
+    ];
+
+    Ok(HealthReport {
+        healthy: files.iter().all(|f| f.status != "corrupt"),
+        files,
+    })
+}
+
+// Schema version of each existing store, read from its top-level "version"
+// field. Stores written before versioning (including plain arrays) report 0.
+#[tauri::command]
+async fn get_store_versions(app: AppHandle) -> Result<HashMap<String, u32>, String> {
+    let app_data_dir = get_data_dir(&app)?;
+
+    let mut versions = HashMap::new();
+    for file_name in STORE_FILES {
+        let path = app_data_dir.join(file_name);
+        if !path.exists() {
+            continue;
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", file_name, e))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", file_name, e))?;
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+
+        versions.insert(file_name.to_string(), version);
+    }
+
+    Ok(versions)
+}
+
+// Backups are folders under app_data_dir/backups/
+fn get_backups_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = get_data_dir(app)?;
+    Ok(app_data_dir.join("backups"))
+}
+
+// Total size of every file below `path`
+fn dir_size(path: &std::path::Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[tauri::command]
+async fn list_backups(app: AppHandle) -> Result<Vec<BackupInfo>, String> {
+    let backups_dir = get_backups_dir(&app)?;
+    if !backups_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries =
+        fs::read_dir(&backups_dir).map_err(|e| format!("Failed to read backups: {}", e))?;
+
+    let mut backups: Vec<BackupInfo> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| BackupInfo {
+            name: entry.file_name().to_string_lossy().to_string(),
+            size_bytes: dir_size(&entry.path()),
+            created_at: entry
+                .metadata()
+                .and_then(|m| m.created())
+                .ok()
+                .map(|time| chrono::DateTime::<chrono::Local>::from(time).to_rfc3339()),
+        })
+        .collect();
+
+    // Newest first
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(backups)
+}
+
+#[tauri::command]
+async fn delete_backup(name: String, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    // Only accept a name taken from the listing, so nothing outside backups/ can be removed
+    let backups = list_backups(app.clone()).await?;
+    if !backups.iter().any(|b| b.name == name) {
+        return Err(format!("Unknown backup '{}'", name));
+    }
+
+    let backup_path = get_backups_dir(&app)?.join(&name);
+    fs::remove_dir_all(&backup_path).map_err(|e| {
+        format!(
+            "Failed to delete backup {}: {}",
+            name,
+            describe_io_error(&e)
+        )
+    })?;
+
+    log::info!("Deleted backup {}", name);
+    Ok(())
+}
+
+// Copy every store in the data directory into a new folder under backups/
+async fn write_backup(app: &AppHandle, name: &str) -> Result<(), String> {
+    let _guard = DATA_LOCK.lock().await;
+
+    let app_data_dir = get_data_dir(app)?;
+    let backup_dir = get_backups_dir(app)?.join(name);
+    if backup_dir.exists() {
+        return Err(format!("Backup {} already exists", name));
+    }
+
+    let files = collect_store_files(&app_data_dir);
+    copy_store_files(&files, &app_data_dir, &backup_dir).inspect_err(|_| {
+        let _ = fs::remove_dir_all(&backup_dir);
+    })
+}
+
+#[tauri::command]
+async fn create_backup(app: AppHandle) -> Result<String, String> {
+    ensure_writable()?;
+
+    let name = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+    write_backup(&app, &name).await?;
+
+    log::info!("Created backup {}", name);
+    Ok(name)
+}
+
+// Take today's automatic backup unless there already is one, then drop the
+// oldest automatic backups. Manual backups are never pruned.
+async fn run_daily_backup(app: &AppHandle) -> Result<(), String> {
+    if is_read_only() || collect_store_files(&get_data_dir(app)?).is_empty() {
+        return Ok(());
+    }
+
+    let backups_dir = get_backups_dir(app)?;
+    let name = format!("auto-{}", chrono::Local::now().format("%Y-%m-%d"));
+    if backups_dir.join(&name).exists() {
+        return Ok(());
+    }
+    write_backup(app, &name).await?;
+    log::info!("Created backup {}", name);
+
+    // Dated names sort chronologically
+    let mut automatic: Vec<String> = list_backups(app.clone())
+        .await?
+        .into_iter()
+        .map(|backup| backup.name)
+        .filter(|name| name.starts_with("auto-"))
+        .collect();
+    automatic.sort();
+    for name in automatic.iter().rev().skip(MAX_AUTO_BACKUPS) {
+        fs::remove_dir_all(backups_dir.join(name))
+            .map_err(|e| format!("Failed to prune backup {}: {}", name, describe_io_error(&e)))?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn enable_autostart(app: AppHandle) -> Result<(), String> {
+    let autostart_manager = app.autolaunch();
+    autostart_manager
+        .enable()
+        .map_err(|e| format!("Failed to enable autostart: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn disable_autostart(app: AppHandle) -> Result<(), String> {
+    let autostart_manager = app.autolaunch();
+    autostart_manager
+        .disable()
+        .map_err(|e| format!("Failed to disable autostart: {}", e))?;
+    Ok(())
+}
+
+// Turn the OS launch entry off for now without touching settings.autostart;
+// resume_autostart or the next launch puts it back if the setting is on
+#[tauri::command]
+async fn suspend_autostart(app: AppHandle) -> Result<(), String> {
+    app.autolaunch()
+        .disable()
+        .map_err(|e| format!("Failed to suspend autostart: {}", e))?;
+    log::info!("Autostart suspended");
+    Ok(())
+}
+
+#[tauri::command]
+async fn resume_autostart(app: AppHandle) -> Result<(), String> {
+    if !load_settings(app.clone()).await?.autostart {
+        return Ok(());
+    }
+    app.autolaunch()
+        .enable()
+        .map_err(|e| format!("Failed to resume autostart: {}", e))?;
+    log::info!("Autostart resumed");
+    Ok(())
+}
+
+#[tauri::command]
+async fn is_autostart_enabled(app: AppHandle) -> Result<bool, String> {
+    let autostart_manager = app.autolaunch();
+    autostart_manager
+        .is_enabled()
+        .map_err(|e| format!("Failed to check autostart status: {}", e))
+}
+
+#[tauri::command]
+async fn save_manual_sessions(
+    sessions: Vec<ManualSession>,
+    allow_long: Option<bool>,
+    app: AppHandle,
+) -> Result<(), String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+    write_manual_sessions(sessions, allow_long, app).await
+}
+
+// Validate and write manual_sessions.json; the caller holds DATA_LOCK
+async fn write_manual_sessions(
+    sessions: Vec<ManualSession>,
+    allow_long: Option<bool>,
+    app: AppHandle,
+) -> Result<(), String> {
+    // Only new sessions and changed durations are checked, so an old session
+    // saved before the limit existed doesn't block unrelated edits
+    let existing: HashMap<String, u32> = load_manual_sessions(app.clone())
+        .await?
+        .into_iter()
+        .map(|s| (s.id, s.duration))
+        .collect();
+    let max_minutes = load_settings(app.clone())
+        .await?
+        .advanced
+        .max_manual_session_minutes;
+    for session in sessions
+        .iter()
+        .filter(|s| existing.get(&s.id) != Some(&s.duration))
+    {
+        validate_session_duration(session.duration, max_minutes, allow_long.unwrap_or(false))?;
+    }
+
+    let app_data_dir = get_data_dir(&app)?;
+
+    // Create the directory if it doesn't exist
+    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let file_path = app_data_dir.join("manual_sessions.json");
+    let json = serde_json::to_string_pretty(&sessions)
+        .map_err(|e| format!("Failed to serialize manual sessions: {}", e))?;
+
+    fs::write(file_path, json)
+        .map_err(|e| format!("Failed to write manual sessions file: {}", e))?;
+    invalidate_stats_cache();
+
+    // Track manual sessions saved analytics (if enabled)
+    if are_analytics_enabled(&app).await {
+        let properties = Some(serde_json::json!({
+            "session_count": sessions.len()
+        }));
+        let _ = app.track_event("manual_sessions_saved", properties);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn load_manual_sessions(app: AppHandle) -> Result<Vec<ManualSession>, String> {
+    let app_data_dir = get_data_dir(&app)?;
+    let file_path = app_data_dir.join("manual_sessions.json");
+
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read manual sessions file: {}", e))?;
+    let sessions: Vec<ManualSession> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse manual sessions: {}", e))?;
+
+    Ok(sessions)
+}
+
+#[tauri::command]
+async fn save_manual_session(
+    session: ManualSession,
+    allow_long: Option<bool>,
+    app: AppHandle,
+) -> Result<(), String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+    upsert_manual_session(session, allow_long, app).await
+}
+
+// Add or replace one manual session; the caller holds DATA_LOCK
+async fn upsert_manual_session(
+    session: ManualSession,
+    allow_long: Option<bool>,
+    app: AppHandle,
+) -> Result<(), String> {
+    // Load existing sessions
+    let mut sessions = load_manual_sessions(app.clone()).await?;
+
+    // Remove existing session with same ID if it exists (for updates)
+    sessions.retain(|s| s.id != session.id);
+
+    // Add the new/updated session
+    sessions.push(session);
+
+    // Save all sessions back; this validates the new duration
+    write_manual_sessions(sessions, allow_long, app).await
+}
+
+// Catch typos like 6000 minutes before they skew every statistic
+fn validate_session_duration(
+    duration: u32,
+    max_minutes: u32,
+    allow_long: bool,
+) -> Result<(), String> {
+    if duration == 0 {
+        return Err("Session duration must be greater than zero".to_string());
+    }
+    if duration > max_minutes && !allow_long {
+        return Err(format!(
+            "Session duration of {} minutes exceeds the maximum of {} minutes",
+            duration, max_minutes
+        ));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn delete_manual_session(session_id: String, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+
+    // Load existing sessions
+    let mut sessions = load_manual_sessions(app.clone()).await?;
+
+    // Remove the session with the specified ID
+    sessions.retain(|s| s.id != session_id);
+
+    // Save the updated sessions back
+    write_manual_sessions(sessions, None, app).await
+}
+
+#[tauri::command]
+async fn get_manual_sessions_for_date(
+    date: String,
+    app: AppHandle,
+) -> Result<Vec<ManualSession>, String> {
+    let sessions = load_manual_sessions(app).await?;
+
+    // Filter sessions for the specified date
+    let filtered_sessions: Vec<ManualSession> =
+        sessions.into_iter().filter(|s| s.date == date).collect();
+
+    Ok(filtered_sessions)
+}
+
+// Most recently logged sessions first, for the activity timeline
+#[tauri::command]
+async fn get_recent_sessions(limit: usize, app: AppHandle) -> Result<Vec<RecentSession>, String> {
+    let sessions = load_manual_sessions(app).await?;
+    Ok(recent_sessions(sessions, limit))
+}
+
+fn recent_sessions(mut sessions: Vec<ManualSession>, limit: usize) -> Vec<RecentSession> {
+    // Compare parsed timestamps so mixed UTC offsets still order correctly
+    sessions.sort_by_key(|s| {
+        std::cmp::Reverse((
+            chrono::DateTime::parse_from_rfc3339(&s.created_at).ok(),
+            s.created_at.clone(),
+        ))
+    });
+
+    sessions
+        .into_iter()
+        .take(limit)
+        .map(|s| RecentSession {
+            id: s.id,
+            date: s.date,
+            session_type: s.session_type,
+            duration: s.duration,
+            tags: s.tags.unwrap_or_default(),
+            notes: s.notes,
+            created_at: s.created_at,
+        })
+        .collect()
+}
+
+// Sessions included in the dashboard's recent activity list
+const DASHBOARD_RECENT_LIMIT: usize = 10;
+
+fn range_summary(
+    manual_sessions: &[ManualSession],
+    daily_minutes: &BTreeMap<chrono::NaiveDate, u32>,
+    today: chrono::NaiveDate,
+    range_days: u32,
+) -> RangeSummary {
+    let in_range = |date: &str| {
+        parse_session_date(date)
+            .map(|date| within_last_days(date, today, range_days))
+            .unwrap_or(false)
+    };
+
+    RangeSummary {
+        focus_minutes: daily_minutes
+            .iter()
+            .filter(|(date, _)| within_last_days(**date, today, range_days))
+            .map(|(_, minutes)| minutes)
+            .sum(),
+        sessions: manual_sessions
+            .iter()
+            .filter(|s| !is_break_session_type(&s.session_type) && in_range(&s.date))
+            .count() as u32,
+        active_days: daily_minutes
+            .iter()
+            .filter(|(date, minutes)| **minutes > 0 && within_last_days(**date, today, range_days))
+            .count() as u32,
+    }
+}
+
+// Focus totals for the last `range_days` days, served from STATS_SUMMARY_CACHE
+// while no store has changed
+#[tauri::command]
+async fn get_stats_summary(range_days: u32, app: AppHandle) -> Result<RangeSummary, String> {
+    let today = chrono::Local::now().date_naive();
+    let key = (today, range_days);
+
+    // Read before loading, so a write racing with this call leaves a stale entry
+    let generation = STATS_GENERATION.load(Ordering::SeqCst);
+    if let Some((cached_generation, summary)) = STATS_SUMMARY_CACHE.lock().unwrap().get(&key) {
+        if *cached_generation == generation {
+            return Ok(summary.clone());
+        }
+    }
+
+    let manual_sessions = load_manual_sessions(app).await?;
+    let daily_minutes = daily_focus_minutes(&manual_sessions);
+    let summary = range_summary(&manual_sessions, &daily_minutes, today, range_days);
+
+    let mut cache = STATS_SUMMARY_CACHE.lock().unwrap();
+    cache.retain(|_, (cached_generation, _)| *cached_generation == generation);
+    cache.insert(key, (generation, summary.clone()));
+    Ok(summary)
+}
+
+// Everything the dashboard shows on load, read from each store once. The
+// sub-results are the same ones the individual commands return.
+#[tauri::command]
+async fn get_dashboard(range_days: u32, app: AppHandle) -> Result<Dashboard, String> {
+    let settings = load_settings(app.clone()).await?;
+    let history = get_stats_history(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app.clone()).await?;
+    let tags = load_tags(app.clone()).await?;
+    let session_tags = load_session_tags(app).await?;
+
+    let today = chrono::Local::now().date_naive();
+    let daily_minutes = daily_focus_minutes(&manual_sessions);
+
+    Ok(Dashboard {
+        summary: range_summary(&manual_sessions, &daily_minutes, today, range_days),
+        tag_totals: aggregate_tag_totals(&session_tags, &tags, &manual_sessions, &[], range_days),
+        goal_pace: goal_pace(&settings.timer, &daily_minutes, today),
+        streak: streak_info(&streak_dates(&history, &manual_sessions), today),
+        recent_sessions: recent_sessions(manual_sessions, DASHBOARD_RECENT_LIMIT),
+    })
+}
+
+// Group manual sessions that share date, start/end time and type. Each group is
+// ordered by created_at, so the first id is the original.
+fn group_duplicate_sessions(sessions: &[ManualSession]) -> Vec<Vec<&ManualSession>> {
+    let mut groups: BTreeMap<(&str, &str, &str, &str), Vec<&ManualSession>> = BTreeMap::new();
+    for session in sessions {
+        groups
+            .entry((
+                &session.date,
+                &session.start_time,
+                &session.end_time,
+                &session.session_type,
+            ))
+            .or_default()
+            .push(session);
+    }
+
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort_by_key(|s| {
+                (
+                    chrono::DateTime::parse_from_rfc3339(&s.created_at).ok(),
+                    s.created_at.clone(),
+                )
+            });
+            group
+        })
+        .collect()
+}
+
+#[tauri::command]
+async fn find_duplicate_sessions(app: AppHandle) -> Result<Vec<Vec<String>>, String> {
+    let sessions = load_manual_sessions(app).await?;
+
+    Ok(group_duplicate_sessions(&sessions)
+        .into_iter()
+        .map(|group| group.into_iter().map(|s| s.id.clone()).collect())
+        .collect())
+}
+
+#[tauri::command]
+async fn dedupe_manual_sessions(app: AppHandle) -> Result<u32, String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+
+    let sessions = load_manual_sessions(app.clone()).await?;
+
+    // Keep the earliest session of each group, drop the rest
+    let duplicate_ids: Vec<String> = group_duplicate_sessions(&sessions)
+        .into_iter()
+        .flat_map(|group| group.into_iter().skip(1).map(|s| s.id.clone()))
+        .collect();
+
+    if duplicate_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let removed = duplicate_ids.len() as u32;
+    let remaining: Vec<ManualSession> = sessions
+        .into_iter()
+        .filter(|s| !duplicate_ids.contains(&s.id))
+        .collect();
+    write_manual_sessions(remaining, None, app).await?;
+
+    Ok(removed)
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::async_runtime::block_on(async {
+        tauri::Builder::default()
+            // Must be registered first: a second launch hands off to this process
+            // and exits before creating its own tray or registering shortcuts
+            .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+                let app_handle = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = show_window(app_handle).await {
+                        log::error!("Failed to focus existing window: {}", e);
+                    }
+                });
+            }))
+            .plugin(tauri_plugin_opener::init())
+            .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+            .plugin(tauri_plugin_dialog::init())
+            .plugin(tauri_plugin_notification::init())
+            .plugin(tauri_plugin_autostart::init(
                 tauri_plugin_autostart::MacosLauncher::LaunchAgent,
                 None,
             ))
@@ -922,460 +5447,1902 @@ pub fn run() {
             .plugin(tauri_plugin_aptabase::Builder::new("A-EU-9457123106").build())
             .invoke_handler(tauri::generate_handler![
                 greet,
+                set_read_only,
+                get_read_only,
+                is_data_dir_fallback,
+                is_primary_instance,
+                get_workspace,
+                list_workspaces,
+                set_workspace,
+                migrate_data_dir,
                 save_session_data,
                 load_session_data,
+                save_timer_state,
+                load_timer_state,
+                clear_timer_state,
+                manual_reset_session,
+                save_session_bundle,
+                session_completed,
                 save_tasks,
+                save_tasks_debounced,
                 load_tasks,
+                import_tasks_text,
+                clear_completed_tasks,
+                get_task_completion_stats,
                 get_stats_history,
                 save_daily_stats,
+                archive_history,
+                rebuild_history_from_sessions,
+                load_history_archive,
+                get_streak_info,
+                streak_min_minutes_today,
+                get_active_dates,
+                get_stats_for_dates,
+                get_rolling_average,
+                estimate_plan_completion,
+                sessions_until_long_break,
+                get_weekday_averages,
+                get_focus_break_ratio,
+                get_goal_pace,
+                get_plan_completion_rate,
+                get_daily_goal_progress,
+                get_current_week,
+                project_week_total,
+                get_consistency_score,
+                get_focus_load,
+                get_break_adherence,
+                time_since_last_session,
+                get_focus_score_today,
+                get_type_breakdown,
+                get_avg_session_gap,
+                get_completion_hour_distribution,
+                get_trend,
+                get_avg_session_length,
+                add_session_note,
                 update_tray_icon,
+                set_session_progress,
+                set_mode_icons,
+                get_battery_status,
                 update_tray_menu,
+                set_tray_attention,
+                schedule_auto_start,
+                cancel_auto_start,
                 show_window,
+                list_monitors,
+                is_window_focused,
+                set_mini_mode,
+                get_system_theme,
+                set_always_on_top,
                 save_settings,
+                validate_timer_settings,
+                get_default_duration,
                 load_settings,
+                get_settings_json,
+                set_debug_mode,
+                emit_test_session_complete,
                 register_global_shortcuts,
                 unregister_global_shortcuts,
+                extend_timer,
+                capture_next_shortcut,
+                get_log_path,
+                set_log_level,
                 reset_all_data,
+                check_data_dir_writable,
+                health_check,
+                get_store_versions,
+                list_backups,
+                create_backup,
+                delete_backup,
+                in_quiet_hours,
+                mute_notifications_until,
+                clear_notification_mute,
+                is_muted,
+                notify_session_complete,
+                play_sound,
+                start_tick_sound,
+                stop_tick_sound,
                 start_activity_monitoring,
                 stop_activity_monitoring,
                 update_activity_timeout,
+                set_threshold_for_mode,
+                debug_idle_source,
                 enable_autostart,
                 disable_autostart,
+                suspend_autostart,
+                resume_autostart,
                 is_autostart_enabled,
                 save_manual_sessions,
                 load_manual_sessions,
                 save_manual_session,
                 delete_manual_session,
                 get_manual_sessions_for_date,
+                get_recent_sessions,
+                get_dashboard,
+                get_stats_summary,
+                find_duplicate_sessions,
+                dedupe_manual_sessions,
                 load_tags,
                 save_tags,
                 save_tag,
+                set_tag_pinned,
                 delete_tag,
                 load_session_tags,
                 save_session_tags,
                 add_session_tag,
+                get_tag_totals,
+                get_top_tag,
+                clear_stats_cache,
+                get_tags_by_recency,
+                get_tag_streak,
+                get_tag_cooccurrence,
+                retag_sessions,
                 write_excel_file,
+                export_joined_sessions,
+                export_events_jsonl,
+                export_monthly_report_pdf,
+                export_tags,
+                export_day,
+                import_manual_sessions_csv,
                 start_oauth_server,
+                stop_oauth_server,
                 set_dock_visibility,
+                set_menubar_only,
                 set_status_bar_visibility
             ])
             .setup(|app| {
+                // Set up the log file sink before anything else can log
+                if let Err(e) = init_logging(app.handle()) {
+                    eprintln!("Failed to initialize logging: {}", e);
+                }
+
+                // PRESTO_READ_ONLY=1 launches in read-only/guest mode
+                if std::env::var("PRESTO_READ_ONLY")
+                    .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+                    .unwrap_or(false)
+                {
+                    READ_ONLY.store(true, Ordering::SeqCst);
+                    log::info!("Starting in read-only mode");
+                }
+
+                // Pick the data directory and workspace before any store is read
+                if let Err(e) = load_data_dir_override(app.handle()) {
+                    log::error!("Failed to restore data directory: {}", e);
+                }
+                if let Err(e) = load_active_workspace(app.handle()) {
+                    log::error!("Failed to restore active workspace: {}", e);
+                }
+                refresh_runtime_settings(app.handle());
+
+                let start_hidden_from_dock = read_settings(app.handle())
+                    .map(|settings| settings.start_hidden_from_dock)
+                    .unwrap_or(false);
+                if start_hidden_from_dock {
+                    if let Err(e) = apply_menubar_only(app.handle(), true) {
+                        log::error!("Failed to start in menu-bar-only mode: {}", e);
+                    }
+                }
+
+                // Undo a suspend_autostart from an earlier run
+                let app_handle_autostart = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = resume_autostart(app_handle_autostart).await {
+                        log::warn!("Failed to restore autostart: {}", e);
+                    }
+                });
+
                 // Track app started event (if enabled)
                 let app_handle_analytics = app.handle().clone();
                 tauri::async_runtime::spawn(async move {
-                    if are_analytics_enabled(&app_handle_analytics).await {
-                        let _ = app_handle_analytics.track_event("app_started", None);
+                    if are_analytics_enabled(&app_handle_analytics).await {
+                        let _ = app_handle_analytics.track_event("app_started", None);
+                    }
+                });
+
+                let show_item =
+                    MenuItem::with_id(app, "show", "Show Presto", true, None::<&str>)?;
+                let start_session_item = MenuItem::with_id(
+                    app,
+                    "start_session",
+                    "Start Session",
+                    false,
+                    None::<&str>,
+                )?;
+                let pause_item = MenuItem::with_id(app, "pause", "Pause", false, None::<&str>)?;
+                let skip_item =
+                    MenuItem::with_id(app, "skip", "Skip Session", false, None::<&str>)?;
+                let cancel_item = MenuItem::with_id(app, "cancel", "Cancel", false, None::<&str>)?;
+                let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+                let menu = Menu::with_items(
+                    app,
+                    &[
+                        &show_item,
+                        &start_session_item,
+                        &pause_item,
+                        &skip_item,
+                        &cancel_item,
+                        &quit_item,
+                    ],
+                )?;
+
+                let app_handle = app.handle().clone();
+                let app_handle_for_click = app_handle.clone();
+
+                let _tray = TrayIconBuilder::with_id("main")
+                    .menu(&menu)
+                    .show_menu_on_left_click(true)
+                    .on_menu_event(move |_tray, event| match event.id.as_ref() {
+                        "show" => {
+                            if let Some(window) = app_handle.get_webview_window("main") {
+                                #[cfg(any(target_os = "windows", target_os = "linux"))]
+                                if !MENUBAR_ONLY.load(Ordering::SeqCst) {
+                                    set_taskbar_visibility(&window, true);
+                                }
+                                clear_tray_attention(&app_handle);
+                                let _ = window.show();
+                                let _ = window.set_focus();
+                            }
+                        }
+                        "start_session" => {
+                            if let Some(window) = app_handle.get_webview_window("main") {
+                                let _ = window.emit("tray-start-session", ());
+                                let _ = window.show();
+                                let _ = window.set_focus();
+                            }
+                        }
+                        "pause" => {
+                            if let Some(window) = app_handle.get_webview_window("main") {
+                                let _ = window.emit("tray-pause", ());
+                                let _ = window.show();
+                                let _ = window.set_focus();
+                            }
+                        }
+                        "skip" => {
+                            if let Some(window) = app_handle.get_webview_window("main") {
+                                let _ = window.emit("tray-skip", ());
+                                let _ = window.show();
+                                let _ = window.set_focus();
+                            }
+                        }
+                        "cancel" => {
+                            if let Some(window) = app_handle.get_webview_window("main") {
+                                let _ = window.emit("tray-cancel", ());
+                                let _ = window.show();
+                                let _ = window.set_focus();
+                            }
+                        }
+                        "quit" => {
+                            app_handle.exit(0);
+                        }
+                        _ => {}
+                    })
+                    .on_tray_icon_event(move |_tray, event| {
+                        if let TrayIconEvent::Click { .. } = event {
+                            if let Some(window) = app_handle_for_click.get_webview_window("main") {
+                                #[cfg(any(target_os = "windows", target_os = "linux"))]
+                                if !MENUBAR_ONLY.load(Ordering::SeqCst) {
+                                    set_taskbar_visibility(&window, true);
+                                }
+                                clear_tray_attention(&app_handle_for_click);
+                                let _ = window.show();
+                                let _ = window.set_focus();
+                            }
+                        }
+                    })
+                    .build(app)?;
+
+                if let Some(window) = app.get_webview_window("main") {
+                    // Open on the preferred display, if one is configured and connected
+                    let preferred_monitor = read_settings(app.handle())
+                        .ok()
+                        .and_then(|settings| settings.preferred_monitor);
+                    position_window_on_preferred_monitor(&window, preferred_monitor.as_deref());
+
+                    let app_handle_for_close = app.handle().clone();
+                    window.on_window_event(move |event| {
+                        // Let the frontend throttle work while the window is in the background
+                        if let tauri::WindowEvent::Focused(focused) = event {
+                            let _ = app_handle_for_close.emit(
+                                if *focused {
+                                    "window-focus"
+                                } else {
+                                    "window-blur"
+                                },
+                                (),
+                            );
+                        }
+
+                        // Let the frontend follow the OS appearance when the theme setting is "system"
+                        if let tauri::WindowEvent::ThemeChanged(theme) = event {
+                            let _ = app_handle_for_close.emit("theme-changed", theme_name(*theme));
+                        }
+
+                        // Forward dropped export files so the frontend can confirm the import
+                        if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop {
+                            paths,
+                            ..
+                        }) = event
+                        {
+                            for path in paths.iter().filter(|p| is_importable_file(p)) {
+                                let _ = app_handle_for_close
+                                    .emit("file-dropped", path.to_string_lossy().to_string());
+                            }
+                        }
+
+                        if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                            // The close can only be prevented synchronously, so read the
+                            // preference here before deciding
+                            let close_behavior = read_settings(&app_handle_for_close)
+                                .map(|settings| settings.close_behavior)
+                                .unwrap_or_else(|_| default_close_behavior());
+
+                            if close_behavior == "quit" {
+                                // Let the window close; the Exit run event flushes analytics
+                                return;
+                            }
+
+                            // Minimize to tray instead of closing
+                            api.prevent_close();
+
+                            // A hidden window shouldn't keep claiming the top spot
+                            if let Some(window) = app_handle_for_close.get_webview_window("main") {
+                                let _ = window.set_always_on_top(false);
+                            }
+
+                            // Check if we should hide the app icon
+                            let app_handle_clone = app_handle_for_close.clone();
+                            tauri::async_runtime::spawn(async move {
+                                match load_settings(app_handle_clone.clone()).await {
+                                    Ok(settings) => {
+                                        if settings.hide_icon_on_close {
+                                            // Hide the window and set app as dock hidden
+                                            if let Some(window) =
+                                                app_handle_clone.get_webview_window("main")
+                                            {
+                                                let _ = window.hide();
+                                                // Use macOS specific API to hide from dock
+                                                #[cfg(target_os = "macos")]
+                                                {
+                                                    let _ = set_dock_visibility(
+                                                        app_handle_clone.clone(),
+                                                        false,
+                                                    )
+                                                    .await;
+                                                }
+                                                // Windows/Linux: remove the taskbar entry
+                                                #[cfg(any(
+                                                    target_os = "windows",
+                                                    target_os = "linux"
+                                                ))]
+                                                {
+                                                    set_taskbar_visibility(&window, false);
+                                                }
+                                            }
+                                        } else {
+                                            // Just hide the window without hiding from dock
+                                            if let Some(window) =
+                                                app_handle_clone.get_webview_window("main")
+                                            {
+                                                let _ = window.hide();
+                                            }
+                                        }
+                                    }
+                                    Err(_) => {
+                                        // Default behavior: just hide the window
+                                        if let Some(window) =
+                                            app_handle_clone.get_webview_window("main")
+                                        {
+                                            let _ = window.hide();
+                                        }
+                                    }
+                                }
+                            });
+                        }
+                    });
+                }
+
+                start_auto_export_scheduler(app.handle().clone());
+                start_battery_monitor(app.handle().clone());
+
+                let app_handle_for_backup = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = run_daily_backup(&app_handle_for_backup).await {
+                        log::error!("Automatic backup failed: {}", e);
+                    }
+                });
+
+                // Forward screen lock/unlock notifications to the frontend
+                #[cfg(target_os = "macos")]
+                {
+                    register_screen_lock_observer(app.handle().clone());
+                }
+
+                // First-run health check: make sure saves will actually work
+                let app_handle_for_health = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = check_data_dir_writable(app_handle_for_health.clone()).await {
+                        log::error!("Data directory health check failed: {}", e);
+                        let _ = app_handle_for_health.emit("data-dir-unwritable", e);
+                    }
+
+                    // Validate every store so the UI can warn before data goes missing
+                    match health_check(app_handle_for_health.clone()).await {
+                        Ok(report) => {
+                            for file in report.files.iter().filter(|f| f.status == "corrupt") {
+                                log::error!(
+                                    "Store {} is corrupt: {}",
+                                    file.file,
+                                    file.detail.as_deref().unwrap_or_default()
+                                );
+                            }
+                            let _ = app_handle_for_health.emit("health-report", report);
+                        }
+                        Err(e) => log::error!("Startup health check failed: {}", e),
+                    }
+                });
+
+                // Load and register global shortcuts
+                let app_handle_for_shortcuts = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    match load_settings(app_handle_for_shortcuts.clone()).await {
+                        Ok(settings) => {
+                            if let Err(e) = register_global_shortcuts(
+                                app_handle_for_shortcuts,
+                                settings.shortcuts,
+                            )
+                            .await
+                            {
+                                log::error!(
+                                    "Failed to register global shortcuts on startup: {}",
+                                    e
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Failed to load settings on startup: {}", e);
+                            // Try to register default shortcuts
+                            let default_settings = AppSettings::default();
+                            if let Err(e) = register_global_shortcuts(
+                                app_handle_for_shortcuts,
+                                default_settings.shortcuts,
+                            )
+                            .await
+                            {
+                                log::error!("Failed to register default global shortcuts: {}", e);
+                            }
+                        }
                     }
                 });
 
-                let show_item =
-                    MenuItem::with_id(app, "show", "Show Presto", true, None::<&str>)?;
-                let start_session_item = MenuItem::with_id(
-                    app,
-                    "start_session",
-                    "Start Session",
-                    false,
-                    None::<&str>,
-                )?;
-                let pause_item = MenuItem::with_id(app, "pause", "Pause", false, None::<&str>)?;
-                let skip_item =
-                    MenuItem::with_id(app, "skip", "Skip Session", false, None::<&str>)?;
-                let cancel_item = MenuItem::with_id(app, "cancel", "Cancel", false, None::<&str>)?;
-                let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-                let menu = Menu::with_items(
-                    app,
-                    &[
-                        &show_item,
-                        &start_session_item,
-                        &pause_item,
-                        &skip_item,
-                        &cancel_item,
-                        &quit_item,
-                    ],
-                )?;
+                Ok(())
+            })
+            .build(tauri::generate_context!())
+            .expect("error while running tauri application")
+            .run(|app_handle, event| match event {
+                tauri::RunEvent::Exit { .. } => {
+                    // Make sure debounced task edits hit the disk before quitting
+                    if let Err(e) = flush_pending_tasks(app_handle) {
+                        log::error!("Failed to flush pending tasks on exit: {}", e);
+                    }
+
+                    // Always track app exit event regardless of analytics settings
+                    // since this is the final event and useful for crash detection.
+                    // Debug mode sessions are the one exception and are never reported.
+                    if !DEBUG_MODE.load(Ordering::SeqCst) {
+                        let _ = app_handle.track_event("app_exited", None);
+                    }
+                    app_handle.flush_events_blocking();
+                    log::logger().flush();
+                }
+                tauri::RunEvent::Reopen { .. } => {
+                    // When the user clicks on the dock icon, show the window
+                    if let Some(window) = app_handle.get_webview_window("main") {
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                        // If the app was previously hidden from dock, restore it,
+                        // unless it's meant to live in the menu bar only
+                        #[cfg(target_os = "macos")]
+                        if !MENUBAR_ONLY.load(Ordering::SeqCst) {
+                            let app_handle_clone = app_handle.clone();
+                            tauri::async_runtime::spawn(async move {
+                                let _ = set_dock_visibility(app_handle_clone, true).await;
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            });
+    })
+}
+
+#[tauri::command]
+async fn load_tags(app: AppHandle) -> Result<Vec<Tag>, String> {
+    let app_data_dir = get_data_dir(&app)?;
+
+    let file_path = app_data_dir.join("tags.json");
+
+    if file_path.exists() {
+        let content =
+            fs::read_to_string(&file_path).map_err(|e| format!("Failed to read tags: {}", e))?;
+        let mut tags: Vec<Tag> = serde_json::from_str(&content).unwrap_or_else(|_| Vec::new());
+        // Pinned tags first, otherwise in stored order
+        tags.sort_by_key(|t| !t.is_pinned());
+        Ok(tags)
+    } else {
+        // Return default focus tag if no tags exist
+        let default_tag = Tag {
+            id: "default-focus".to_string(),
+            name: "Focus".to_string(),
+            icon: "ri-brain-line".to_string(),
+            color: "#4CAF50".to_string(),
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                .to_string(),
+            pinned: None,
+        };
+        Ok(vec![default_tag])
+    }
+}
+
+#[tauri::command]
+async fn save_tags(tags: Vec<Tag>, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+    write_tags(tags, app).await
+}
+
+// Write tags.json; the caller holds DATA_LOCK
+async fn write_tags(tags: Vec<Tag>, app: AppHandle) -> Result<(), String> {
+    let app_data_dir = get_data_dir(&app)?;
+
+    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let file_path = app_data_dir.join("tags.json");
+    let json = serde_json::to_string_pretty(&tags)
+        .map_err(|e| format!("Failed to serialize tags: {}", e))?;
+    fs::write(file_path, json).map_err(|e| format!("Failed to write tags file: {}", e))?;
+    invalidate_stats_cache();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn save_tag(tag: Tag, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+
+    let mut tags = load_tags(app.clone()).await?;
+
+    // Remove existing tag with same ID if it exists (for updates)
+    tags.retain(|t| t.id != tag.id);
+
+    // Add the new/updated tag
+    tags.push(tag);
+
+    // Save all tags back
+    write_tags(tags, app).await
+}
+
+#[tauri::command]
+async fn set_tag_pinned(tag_id: String, pinned: bool, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+
+    let mut tags = load_tags(app.clone()).await?;
+    let tag = tags
+        .iter_mut()
+        .find(|t| t.id == tag_id)
+        .ok_or_else(|| format!("Tag not found: {}", tag_id))?;
+    tag.pinned = Some(pinned);
+
+    write_tags(tags, app).await
+}
+
+#[tauri::command]
+async fn delete_tag(tag_id: String, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+
+    let mut tags = load_tags(app.clone()).await?;
+
+    // Remove the tag with the specified ID
+    tags.retain(|t| t.id != tag_id);
+
+    // Save the updated tags back
+    write_tags(tags, app).await
+}
+
+#[tauri::command]
+async fn load_session_tags(app: AppHandle) -> Result<Vec<SessionTag>, String> {
+    let app_data_dir = get_data_dir(&app)?;
+
+    let file_path = app_data_dir.join("session_tags.json");
+
+    if file_path.exists() {
+        let content = fs::read_to_string(&file_path)
+            .map_err(|e| format!("Failed to read session tags: {}", e))?;
+        Ok(serde_json::from_str(&content).unwrap_or_else(|_| Vec::new()))
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+#[tauri::command]
+async fn save_session_tags(session_tags: Vec<SessionTag>, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+    write_session_tags(session_tags, app).await
+}
+
+// Write session_tags.json; the caller holds DATA_LOCK
+async fn write_session_tags(session_tags: Vec<SessionTag>, app: AppHandle) -> Result<(), String> {
+    let app_data_dir = get_data_dir(&app)?;
 
-                let app_handle = app.handle().clone();
-                let app_handle_for_click = app_handle.clone();
+    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
 
-                let _tray = TrayIconBuilder::with_id("main")
-                    .menu(&menu)
-                    .show_menu_on_left_click(true)
-                    .on_menu_event(move |_tray, event| match event.id.as_ref() {
-                        "show" => {
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                        "start_session" => {
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.emit("tray-start-session", ());
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                        "pause" => {
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.emit("tray-pause", ());
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                        "skip" => {
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.emit("tray-skip", ());
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                        "cancel" => {
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.emit("tray-cancel", ());
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                        "quit" => {
-                            app_handle.exit(0);
-                        }
-                        _ => {}
-                    })
-                    .on_tray_icon_event(move |_tray, event| {
-                        if let TrayIconEvent::Click { .. } = event {
-                            if let Some(window) = app_handle_for_click.get_webview_window("main") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                    })
-                    .build(app)?;
+    let file_path = app_data_dir.join("session_tags.json");
+    let json = serde_json::to_string_pretty(&session_tags)
+        .map_err(|e| format!("Failed to serialize session tags: {}", e))?;
+    fs::write(file_path, json).map_err(|e| format!("Failed to write session tags file: {}", e))?;
+    invalidate_stats_cache();
 
-                if let Some(window) = app.get_webview_window("main") {
-                    let app_handle_for_close = app.handle().clone();
-                    window.on_window_event(move |event| {
-                        if let tauri::WindowEvent::CloseRequested { api, .. } = event {
-                            // Always prevent close
-                            api.prevent_close();
+    Ok(())
+}
+
+#[tauri::command]
+async fn add_session_tag(session_tag: SessionTag, app: AppHandle) -> Result<(), String> {
+    ensure_writable()?;
+
+    let _guard = DATA_LOCK.lock().await;
+
+    let mut session_tags = load_session_tags(app.clone()).await?;
+    session_tags.push(session_tag);
+    write_session_tags(session_tags, app).await
+}
+
+// Session tags store an ISO timestamp; convert it to the local calendar day
+fn parse_iso_local_date(timestamp: &str) -> Option<chrono::NaiveDate> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Local).date_naive())
+}
+
+// Sum SessionTag durations per tag over the last `days` days, largest first.
+// Tags on manual sessions whose type isn't in `types` are left out.
+fn aggregate_tag_totals(
+    session_tags: &[SessionTag],
+    tags: &[Tag],
+    manual_sessions: &[ManualSession],
+    types: &[String],
+    days: u32,
+) -> Vec<TagTotal> {
+    let today = chrono::Local::now().date_naive();
+    let mut totals: HashMap<String, TagTotal> = HashMap::new();
+
+    let excluded_sessions: BTreeSet<&str> = manual_sessions
+        .iter()
+        .filter(|s| !matches_session_types(types, &s.session_type))
+        .map(|s| s.id.as_str())
+        .collect();
+
+    for session_tag in session_tags
+        .iter()
+        .filter(|st| !excluded_sessions.contains(st.session_id.as_str()))
+    {
+        let Some(date) = parse_iso_local_date(&session_tag.created_at) else {
+            continue;
+        };
+        if !within_last_days(date, today, days) {
+            continue;
+        }
+
+        let total = totals.entry(session_tag.tag_id.clone()).or_insert_with(|| {
+            let tag = tags.iter().find(|t| t.id == session_tag.tag_id);
+            TagTotal {
+                tag_id: session_tag.tag_id.clone(),
+                name: tag.map(|t| t.name.clone()).unwrap_or_default(),
+                icon: tag.map(|t| t.icon.clone()).unwrap_or_default(),
+                color: tag.map(|t| t.color.clone()).unwrap_or_default(),
+                total_duration: 0,
+                session_count: 0,
+                last_used: None,
+            }
+        });
+
+        total.total_duration += session_tag.duration as u64;
+        total.session_count += 1;
+        if total.last_used.as_deref() < Some(session_tag.created_at.as_str()) {
+            total.last_used = Some(session_tag.created_at.clone());
+        }
+    }
+
+    let mut totals: Vec<TagTotal> = totals.into_values().collect();
+    totals.sort_by(|a, b| {
+        b.total_duration
+            .cmp(&a.total_duration)
+            .then_with(|| b.last_used.cmp(&a.last_used))
+    });
+    totals
+}
+
+// Manual sessions embed copies of their tags; match them by id
+fn manual_session_has_tag(session: &ManualSession, tag_id: &str) -> bool {
+    session
+        .tags
+        .as_deref()
+        .unwrap_or(&[])
+        .iter()
+        .any(|tag| tag.get("id").and_then(|v| v.as_str()) == Some(tag_id))
+}
+
+// Add (and optionally remove) a tag on every manual session matching the
+// filter: sessions carrying filter_tag_id, dated within date_from..=date_to.
+// Both the embedded tag copies and the session_tags.json links are updated.
+// Returns how many sessions changed.
+#[tauri::command]
+async fn retag_sessions(
+    filter_tag_id: Option<String>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    add_tag_id: String,
+    remove_tag_id: Option<String>,
+    app: AppHandle,
+) -> Result<u32, String> {
+    ensure_writable()?;
+
+    let date_from = date_from.as_deref().map(parse_date_arg).transpose()?;
+    let date_to = date_to.as_deref().map(parse_date_arg).transpose()?;
+
+    let _guard = DATA_LOCK.lock().await;
+
+    let tags = load_tags(app.clone()).await?;
+    let add_tag = tags
+        .iter()
+        .find(|t| t.id == add_tag_id)
+        .ok_or_else(|| format!("Tag not found: {}", add_tag_id))?;
+    let add_tag_value =
+        serde_json::to_value(add_tag).map_err(|e| format!("Failed to serialize tag: {}", e))?;
+
+    let mut manual_sessions = load_manual_sessions(app.clone()).await?;
+    let mut session_tags = load_session_tags(app.clone()).await?;
+    let links_before = session_tags.len();
+    let mut links_added = false;
+    let mut changed = 0;
+
+    for session in manual_sessions.iter_mut() {
+        if let Some(filter_tag_id) = &filter_tag_id {
+            if !manual_session_has_tag(session, filter_tag_id) {
+                continue;
+            }
+        }
+        let Some(date) = parse_session_date(&session.date) else {
+            continue;
+        };
+        if date_from.is_some_and(|from| date < from) || date_to.is_some_and(|to| date > to) {
+            continue;
+        }
+
+        let mut session_changed = false;
+
+        if let Some(remove_tag_id) = remove_tag_id.as_deref().filter(|id| *id != add_tag_id) {
+            if manual_session_has_tag(session, remove_tag_id) {
+                if let Some(embedded) = session.tags.as_mut() {
+                    embedded.retain(|tag| {
+                        tag.get("id").and_then(|v| v.as_str()) != Some(remove_tag_id)
+                    });
+                }
+                session_changed = true;
+            }
+            session_tags.retain(|st| !(st.session_id == session.id && st.tag_id == remove_tag_id));
+        }
+
+        if !manual_session_has_tag(session, &add_tag_id) {
+            session
+                .tags
+                .get_or_insert_with(Vec::new)
+                .push(add_tag_value.clone());
+            session_changed = true;
+        }
+        let linked = session_tags
+            .iter()
+            .any(|st| st.session_id == session.id && st.tag_id == add_tag_id);
+        if !linked {
+            // Dated like the session so per-period tag totals pick it up
+            session_tags.push(SessionTag {
+                session_id: session.id.clone(),
+                tag_id: add_tag_id.clone(),
+                duration: session.duration * 60,
+                created_at: session.created_at.clone(),
+            });
+            links_added = true;
+        }
+
+        if session_changed {
+            changed += 1;
+        }
+    }
+
+    if changed > 0 {
+        write_manual_sessions(manual_sessions, None, app.clone()).await?;
+    }
+    if links_added || session_tags.len() != links_before {
+        write_session_tags(session_tags, app).await?;
+    }
+
+    Ok(changed)
+}
+
+// Count unordered pairs of distinct tags appearing on the same manual session,
+// most frequent first
+fn count_tag_pairs(manual_sessions: &[ManualSession]) -> Vec<TagPair> {
+    let mut counts: BTreeMap<(String, String), u32> = BTreeMap::new();
+
+    for session in manual_sessions {
+        // A set drops repeated tags, so there are no self-pairs, and keeps ids sorted
+        let tag_ids: BTreeSet<&str> = session
+            .tags
+            .as_deref()
+            .unwrap_or(&[])
+            .iter()
+            .filter_map(|tag| tag.get("id").and_then(|v| v.as_str()))
+            .collect();
+        let tag_ids: Vec<&str> = tag_ids.into_iter().collect();
+
+        for (index, tag_a) in tag_ids.iter().enumerate() {
+            for tag_b in &tag_ids[index + 1..] {
+                *counts
+                    .entry((tag_a.to_string(), tag_b.to_string()))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    let mut pairs: Vec<TagPair> = counts
+        .into_iter()
+        .map(|((tag_a, tag_b), count)| TagPair {
+            tag_a,
+            tag_b,
+            count,
+        })
+        .collect();
+    // Stable sort keeps equal counts in id order
+    pairs.sort_by(|a, b| b.count.cmp(&a.count));
+    pairs
+}
+
+#[tauri::command]
+async fn get_tag_cooccurrence(
+    limit: Option<usize>,
+    app: AppHandle,
+) -> Result<Vec<TagPair>, String> {
+    let manual_sessions = load_manual_sessions(app).await?;
+
+    let mut pairs = count_tag_pairs(&manual_sessions);
+    if let Some(limit) = limit {
+        pairs.truncate(limit);
+    }
+    Ok(pairs)
+}
+
+#[tauri::command]
+async fn get_tag_streak(tag_id: String, app: AppHandle) -> Result<StreakInfo, String> {
+    let tags = load_tags(app.clone()).await?;
+
+    // A deleted tag has no streak, even if old sessions still reference it
+    if !tags.iter().any(|t| t.id == tag_id) {
+        return Ok(StreakInfo {
+            current_streak: 0,
+            longest_streak: 0,
+            last_active_date: None,
+        });
+    }
+
+    let session_tags = load_session_tags(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app).await?;
+
+    let mut active_dates: BTreeSet<chrono::NaiveDate> = session_tags
+        .iter()
+        .filter(|st| st.tag_id == tag_id)
+        .filter_map(|st| parse_iso_local_date(&st.created_at))
+        .collect();
+    active_dates.extend(
+        manual_sessions
+            .iter()
+            .filter(|s| manual_session_has_tag(s, &tag_id))
+            .filter_map(|s| parse_session_date(&s.date)),
+    );
+
+    let today = chrono::Local::now().date_naive();
+    let (current_streak, longest_streak) = compute_streaks(&active_dates, today);
+
+    Ok(StreakInfo {
+        current_streak,
+        longest_streak,
+        last_active_date: active_dates
+            .iter()
+            .next_back()
+            .map(|d| format_session_date(*d)),
+    })
+}
+
+fn invalidate_stats_cache() {
+    STATS_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+#[tauri::command]
+fn clear_stats_cache() {
+    TAG_TOTALS_CACHE.lock().unwrap().clear();
+    STATS_SUMMARY_CACHE.lock().unwrap().clear();
+    invalidate_stats_cache();
+}
+
+// Served from TAG_TOTALS_CACHE while no tag store has changed; the day is part
+// of the key since the window is relative to today
+#[tauri::command]
+async fn get_tag_totals(
+    days: u32,
+    types: Option<Vec<String>>,
+    app: AppHandle,
+) -> Result<Vec<TagTotal>, String> {
+    let mut types = types.unwrap_or_default();
+    types.sort();
+    types.dedup();
+    let key = (chrono::Local::now().date_naive(), days, types);
+
+    // Read before loading, so a write racing with this call leaves a stale entry
+    let generation = STATS_GENERATION.load(Ordering::SeqCst);
+    if let Some((cached_generation, totals)) = TAG_TOTALS_CACHE.lock().unwrap().get(&key) {
+        if *cached_generation == generation {
+            return Ok(totals.clone());
+        }
+    }
+
+    let session_tags = load_session_tags(app.clone()).await?;
+    let tags = load_tags(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app).await?;
+
+    let totals = aggregate_tag_totals(&session_tags, &tags, &manual_sessions, &key.2, days);
+
+    let mut cache = TAG_TOTALS_CACHE.lock().unwrap();
+    // Entries from older generations can never be served again
+    cache.retain(|_, (cached_generation, _)| *cached_generation == generation);
+    cache.insert(key, (generation, totals.clone()));
+    Ok(totals)
+}
+
+#[tauri::command]
+async fn get_top_tag(
+    days: u32,
+    types: Option<Vec<String>>,
+    app: AppHandle,
+) -> Result<Option<TagTotal>, String> {
+    // Totals are sorted by duration, ties broken by the most recent session
+    let totals = get_tag_totals(days, types, app).await?;
+    Ok(totals.into_iter().find(|t| t.total_duration > 0))
+}
+
+// Tag timestamps are either ISO strings or, for the built-in default tag,
+// unix seconds; normalize both to milliseconds
+fn timestamp_millis(timestamp: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|dt| dt.timestamp_millis())
+        .ok()
+        .or_else(|| timestamp.parse::<i64>().ok().map(|secs| secs * 1000))
+}
+
+#[tauri::command]
+async fn get_tags_by_recency(app: AppHandle) -> Result<Vec<Tag>, String> {
+    let tags = load_tags(app.clone()).await?;
+    let session_tags = load_session_tags(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app).await?;
+
+    // Most recent use of each tag, from the join and from embedded manual-session tags
+    let mut last_used: HashMap<&str, i64> = HashMap::new();
+    let mut record_use = |tag_id: &str, created_at: &str| {
+        if let (Some(tag), Some(millis)) = (
+            tags.iter().find(|t| t.id == tag_id),
+            timestamp_millis(created_at),
+        ) {
+            let entry = last_used.entry(tag.id.as_str()).or_insert(millis);
+            *entry = (*entry).max(millis);
+        }
+    };
+    for session_tag in &session_tags {
+        record_use(&session_tag.tag_id, &session_tag.created_at);
+    }
+    for session in &manual_sessions {
+        for tag in session.tags.as_deref().unwrap_or(&[]) {
+            if let Some(tag_id) = tag.get("id").and_then(|v| v.as_str()) {
+                record_use(tag_id, &session.created_at);
+            }
+        }
+    }
+
+    // Used tags first, newest use first; never-used tags after, newest first
+    let mut ordered = tags.clone();
+    ordered.sort_by_key(|tag| match last_used.get(tag.id.as_str()) {
+        Some(&millis) => (0, std::cmp::Reverse(millis)),
+        None => (
+            1,
+            std::cmp::Reverse(timestamp_millis(&tag.created_at).unwrap_or(i64::MIN)),
+        ),
+    });
+    Ok(ordered)
+}
+
+#[tauri::command]
+async fn update_tray_menu(
+    app: AppHandle,
+    is_running: bool,
+    is_paused: bool,
+    current_mode: String,
+    completed_sessions: Option<i32>,
+    total_sessions: Option<i32>,
+) -> Result<(), String> {
+    let tray = app.tray_by_id("main");
+
+    if let Some(tray) = tray {
+        // Create session progress text if available
+        let session_progress = if let (Some(completed), Some(total)) = (completed_sessions, total_sessions) {
+            format!(" ({}/{})", completed, total)
+        } else {
+            String::new()
+        };
+        
+        let show_item = MenuItem::with_id(&app, "show", &format!("Show Presto{}", session_progress), true, None::<&str>)
+            .map_err(|e| format!("Failed to create show item: {}", e))?;
+
+        // Start Session: enabled only if not running
+        let start_session_item = MenuItem::with_id(
+            &app,
+            "start_session",
+            "Start Session",
+            !is_running,
+            None::<&str>,
+        )
+        .map_err(|e| format!("Failed to create start session item: {}", e))?;
+
+        // Pause: enabled only if running and not paused
+        let pause_item = MenuItem::with_id(
+            &app,
+            "pause",
+            "Pause",
+            is_running && !is_paused,
+            None::<&str>,
+        )
+        .map_err(|e| format!("Failed to create pause item: {}", e))?;
+
+        // Skip: enabled only if running
+        let skip_item = MenuItem::with_id(&app, "skip", "Skip Session", is_running, None::<&str>)
+            .map_err(|e| format!("Failed to create skip item: {}", e))?;
+
+        // Cancel: enabled if in focus mode, disabled in break/longBreak (undo)
+        let cancel_text = if current_mode == "focus" {
+            "Cancel"
+        } else {
+            "Cancel Last"
+        };
+        let cancel_item = MenuItem::with_id(&app, "cancel", cancel_text, true, None::<&str>)
+            .map_err(|e| format!("Failed to create cancel item: {}", e))?;
+
+        let quit_item = MenuItem::with_id(&app, "quit", "Quit", true, None::<&str>)
+            .map_err(|e| format!("Failed to create quit item: {}", e))?;
 
-                            // Check if we should hide the app icon
-                            let app_handle_clone = app_handle_for_close.clone();
-                            tauri::async_runtime::spawn(async move {
-                                match load_settings(app_handle_clone.clone()).await {
-                                    Ok(settings) => {
-                                        if settings.hide_icon_on_close {
-                                            // Hide the window and set app as dock hidden
-                                            if let Some(window) =
-                                                app_handle_clone.get_webview_window("main")
-                                            {
-                                                let _ = window.hide();
-                                                // Use macOS specific API to hide from dock
-                                                #[cfg(target_os = "macos")]
-                                                {
-                                                    let _ = set_dock_visibility(
-                                                        app_handle_clone.clone(),
-                                                        false,
-                                                    )
-                                                    .await;
-                                                }
-                                            }
-                                        } else {
-                                            // Just hide the window without hiding from dock
-                                            if let Some(window) =
-                                                app_handle_clone.get_webview_window("main")
-                                            {
-                                                let _ = window.hide();
-                                            }
-                                        }
-                                    }
-                                    Err(_) => {
-                                        // Default behavior: just hide the window
-                                        if let Some(window) =
-                                            app_handle_clone.get_webview_window("main")
-                                        {
-                                            let _ = window.hide();
-                                        }
-                                    }
-                                }
-                            });
-                        }
-                    });
-                }
+        let new_menu = Menu::with_items(
+            &app,
+            &[
+                &show_item,
+                &start_session_item,
+                &pause_item,
+                &skip_item,
+                &cancel_item,
+                &quit_item,
+            ],
+        )
+        .map_err(|e| format!("Failed to create menu: {}", e))?;
 
-                // Load and register global shortcuts
-                let app_handle_for_shortcuts = app.handle().clone();
-                tauri::async_runtime::spawn(async move {
-                    match load_settings(app_handle_for_shortcuts.clone()).await {
-                        Ok(settings) => {
-                            if let Err(e) = register_global_shortcuts(
-                                app_handle_for_shortcuts,
-                                settings.shortcuts,
-                            )
-                            .await
-                            {
-                                eprintln!("Failed to register global shortcuts on startup: {}", e);
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to load settings on startup: {}", e);
-                            // Try to register default shortcuts
-                            let default_settings = AppSettings::default();
-                            if let Err(e) = register_global_shortcuts(
-                                app_handle_for_shortcuts,
-                                default_settings.shortcuts,
-                            )
-                            .await
-                            {
-                                eprintln!("Failed to register default global shortcuts: {}", e);
-                            }
-                        }
-                    }
-                });
+        tray.set_menu(Some(new_menu))
+            .map_err(|e| format!("Failed to set tray menu: {}", e))?;
+    } else {
+        return Err(TRAY_NOT_AVAILABLE.to_string());
+    }
 
-                Ok(())
-            })
-            .build(tauri::generate_context!())
-            .expect("error while running tauri application")
-            .run(|app_handle, event| match event {
-                tauri::RunEvent::Exit { .. } => {
-                    // Always track app exit event regardless of analytics settings
-                    // since this is the final event and useful for crash detection
-                    let _ = app_handle.track_event("app_exited", None);
-                    app_handle.flush_events_blocking();
-                }
-                tauri::RunEvent::Reopen { .. } => {
-                    // When the user clicks on the dock icon, show the window
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                        // If the app was previously hidden from dock, restore it
-                        #[cfg(target_os = "macos")]
-                        {
-                            let app_handle_clone = app_handle.clone();
-                            tauri::async_runtime::spawn(async move {
-                                let _ = set_dock_visibility(app_handle_clone, true).await;
-                            });
-                        }
-                    }
-                }
-                _ => {}
-            });
-    })
+    Ok(())
+}
+
+// Only exported archives and JSON files can be imported by dropping them
+fn is_importable_file(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("zip") || ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false)
 }
 
 #[tauri::command]
-async fn load_tags(app: AppHandle) -> Result<Vec<Tag>, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+async fn write_excel_file(path: String, data: String) -> Result<(), String> {
+    // Decode base64 data
+    let decoded_data = general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| format!("Failed to decode base64 data: {}", e))?;
 
-    let file_path = app_data_dir.join("tags.json");
+    // Write the binary data to file
+    fs::write(&path, decoded_data)
+        .map_err(|e| format!("Failed to write Excel file to {}: {}", path, e))?;
 
-    if file_path.exists() {
-        let content =
-            fs::read_to_string(&file_path).map_err(|e| format!("Failed to read tags: {}", e))?;
-        Ok(serde_json::from_str(&content).unwrap_or_else(|_| Vec::new()))
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct JoinedSessionRow {
+    date: String,
+    start_time: String,
+    end_time: String,
+    duration: u32,
+    session_type: String,
+    tag_name: String,
+    tag_color: String,
+    notes: String,
+}
+
+// Quote a CSV field only when it contains a delimiter, quote or newline
+fn csv_escape(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
     } else {
-        // Return default focus tag if no tags exist
-        let default_tag = Tag {
-            id: "default-focus".to_string(),
-            name: "Focus".to_string(),
-            icon: "ri-brain-line".to_string(),
-            color: "#4CAF50".to_string(),
-            created_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-                .to_string(),
-        };
-        Ok(vec![default_tag])
+        field.to_string()
     }
 }
 
 #[tauri::command]
-async fn save_tags(tags: Vec<Tag>, app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+async fn export_joined_sessions(
+    path: String,
+    format: String,
+    app: AppHandle,
+) -> Result<(), String> {
+    let sessions = load_manual_sessions(app.clone()).await?;
+    let tags = load_tags(app).await?;
+
+    let mut rows: Vec<JoinedSessionRow> = Vec::new();
+    for session in &sessions {
+        let base_row = JoinedSessionRow {
+            date: session.date.clone(),
+            start_time: session.start_time.clone(),
+            end_time: session.end_time.clone(),
+            duration: session.duration,
+            session_type: session.session_type.clone(),
+            tag_name: String::new(),
+            tag_color: String::new(),
+            notes: session.notes.clone().unwrap_or_default(),
+        };
 
-    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+        let session_tags = session.tags.as_deref().unwrap_or(&[]);
+        if session_tags.is_empty() {
+            rows.push(base_row);
+            continue;
+        }
 
-    let file_path = app_data_dir.join("tags.json");
-    let json = serde_json::to_string_pretty(&tags)
-        .map_err(|e| format!("Failed to serialize tags: {}", e))?;
-    fs::write(file_path, json).map_err(|e| format!("Failed to write tags file: {}", e))?;
+        for tag_value in session_tags {
+            // Prefer the current tag definition, fall back to the copy stored on the session
+            let tag_id = tag_value.get("id").and_then(|v| v.as_str());
+            let known_tag = tag_id.and_then(|id| tags.iter().find(|t| t.id == id));
+            let field = |key: &str| {
+                tag_value
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+
+            rows.push(JoinedSessionRow {
+                tag_name: known_tag
+                    .map(|t| t.name.clone())
+                    .unwrap_or_else(|| field("name")),
+                tag_color: known_tag
+                    .map(|t| t.color.clone())
+                    .unwrap_or_else(|| field("color")),
+                ..base_row.clone()
+            });
+        }
+    }
+
+    let content = match format.as_str() {
+        "csv" => {
+            let mut csv = String::from(
+                "date,start_time,end_time,duration,session_type,tag_name,tag_color,notes\n",
+            );
+            for row in &rows {
+                let fields = [
+                    csv_escape(&row.date),
+                    csv_escape(&row.start_time),
+                    csv_escape(&row.end_time),
+                    row.duration.to_string(),
+                    csv_escape(&row.session_type),
+                    csv_escape(&row.tag_name),
+                    csv_escape(&row.tag_color),
+                    csv_escape(&row.notes),
+                ];
+                csv.push_str(&fields.join(","));
+                csv.push('\n');
+            }
+            csv
+        }
+        "json" => serde_json::to_string_pretty(&rows)
+            .map_err(|e| format!("Failed to serialize joined sessions: {}", e))?,
+        other => return Err(format!("Unsupported export format '{}'", other)),
+    };
+
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write export file to {}: {}", path, e))?;
 
     Ok(())
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+struct DayExport {
+    date: String,
+    session: Option<PomodoroSession>, // the day's entry in history.json
+    manual_sessions: Vec<ManualSession>,
+    note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct TagUsage {
+    #[serde(flatten)]
+    tag: Tag,
+    usage_count: u32, // session_tags.json links to this tag
+    total_minutes: u64,
+}
+
+// Every tag with how often and how long it was used, most used first, to help
+// decide which tags to prune or merge. Unused tags are included with zeros.
 #[tauri::command]
-async fn save_tag(tag: Tag, app: AppHandle) -> Result<(), String> {
-    let mut tags = load_tags(app.clone()).await?;
+async fn export_tags(path: String, app: AppHandle) -> Result<(), String> {
+    let tags = load_tags(app.clone()).await?;
+    let session_tags = load_session_tags(app).await?;
+
+    let mut usage: Vec<TagUsage> = tags
+        .into_iter()
+        .map(|tag| {
+            let links = session_tags.iter().filter(|st| st.tag_id == tag.id);
+            TagUsage {
+                usage_count: links.clone().count() as u32,
+                total_minutes: links.map(|st| st.duration as u64).sum::<u64>() / 60,
+                tag,
+            }
+        })
+        .collect();
+    usage.sort_by(|a, b| {
+        b.usage_count
+            .cmp(&a.usage_count)
+            .then_with(|| b.total_minutes.cmp(&a.total_minutes))
+    });
 
-    // Remove existing tag with same ID if it exists (for updates)
-    tags.retain(|t| t.id != tag.id);
+    let json = serde_json::to_string_pretty(&usage)
+        .map_err(|e| format!("Failed to serialize tag export: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write tag export to {}: {}", path, e))?;
 
-    // Add the new/updated tag
-    tags.push(tag);
+    Ok(())
+}
 
-    // Save all tags back
-    save_tags(tags, app).await
+// Make sure the auto-export folder exists and accepts new files
+fn validate_auto_export_dir(path: &str) -> Result<(), String> {
+    let dir = std::path::Path::new(path);
+    if path.trim().is_empty() || !dir.is_dir() {
+        return Err(format!("Auto-export folder not found: {}", path));
+    }
+
+    let probe_path = dir.join(".presto_write_probe");
+    fs::write(&probe_path, "presto write probe").map_err(|e| {
+        format!(
+            "Cannot write to auto-export folder {}: {}",
+            dir.display(),
+            describe_io_error(&e)
+        )
+    })?;
+    let _ = fs::remove_file(probe_path);
+    Ok(())
+}
+
+// Every store zipped up as is, under its own file name
+fn build_full_export(data_dir: &std::path::Path) -> Result<Vec<u8>, String> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for file_name in STORE_FILES {
+        let path = data_dir.join(file_name);
+        if !path.exists() {
+            continue;
+        }
+        let content =
+            fs::read(&path).map_err(|e| format!("Failed to read {}: {}", file_name, e))?;
+        zip.start_file(*file_name, options)
+            .and_then(|_| zip.write_all(&content).map_err(Into::into))
+            .map_err(|e| format!("Failed to add {} to export: {}", file_name, e))?;
+    }
+
+    zip.finish()
+        .map(|cursor| cursor.into_inner())
+        .map_err(|e| format!("Failed to finish export archive: {}", e))
+}
+
+async fn run_auto_export(app: &AppHandle, advanced: &AdvancedSettings) -> Result<PathBuf, String> {
+    validate_auto_export_dir(&advanced.auto_export_path)?;
+
+    let file_name = if advanced.auto_export_timestamped {
+        format!(
+            "presto-export-{}.zip",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        )
+    } else {
+        "presto-export.zip".to_string()
+    };
+    let path = std::path::Path::new(&advanced.auto_export_path).join(file_name);
+
+    let export = {
+        let _guard = DATA_LOCK.lock().await;
+        build_full_export(&get_data_dir(app)?)?
+    };
+    write_file_atomic(&path, &export, false)
+        .map_err(|e| format!("Failed to write export to {}: {}", path.display(), e))?;
+
+    Ok(path)
+}
+
+// Background loop behind auto_export_enabled. Settings are re-read on every
+// check, so changes apply without a restart; the first export runs right
+// after startup.
+fn start_auto_export_scheduler(app: AppHandle) {
+    thread::spawn(move || loop {
+        if let Ok(settings) = tauri::async_runtime::block_on(load_settings(app.clone())) {
+            let advanced = settings.advanced;
+            let interval =
+                Duration::from_secs(advanced.auto_export_interval_hours.max(1) as u64 * 3600);
+            let due = LAST_AUTO_EXPORT
+                .lock()
+                .unwrap()
+                .map(|last| last.elapsed() >= interval)
+                .unwrap_or(true);
+
+            if advanced.auto_export_enabled && due {
+                match tauri::async_runtime::block_on(run_auto_export(&app, &advanced)) {
+                    Ok(path) => log::info!("Auto-exported data to {}", path.display()),
+                    Err(e) => log::error!("Auto-export failed: {}", e),
+                }
+                // Failed runs wait a full interval too, instead of retrying every minute
+                *LAST_AUTO_EXPORT.lock().unwrap() = Some(Instant::now());
+            }
+        }
+
+        thread::sleep(AUTO_EXPORT_CHECK_INTERVAL);
+    });
 }
 
 #[tauri::command]
-async fn delete_tag(tag_id: String, app: AppHandle) -> Result<(), String> {
-    let mut tags = load_tags(app.clone()).await?;
+async fn export_day(date: String, path: String, app: AppHandle) -> Result<(), String> {
+    let date = format_session_date(parse_date_arg(&date)?);
+
+    let history = get_stats_history(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app.clone()).await?;
+    let tags = load_tags(app).await?;
+
+    let session = history.into_iter().find(|s| s.date == date);
+
+    // Swap the tag copies stored on each session for the current definitions
+    let manual_sessions: Vec<ManualSession> = manual_sessions
+        .into_iter()
+        .filter(|s| s.date == date)
+        .map(|mut session| {
+            if let Some(session_tags) = session.tags.as_mut() {
+                for tag_value in session_tags.iter_mut() {
+                    let tag_id = tag_value.get("id").and_then(|v| v.as_str());
+                    if let Some(tag) = tag_id.and_then(|id| tags.iter().find(|t| t.id == id)) {
+                        if let Ok(resolved) = serde_json::to_value(tag) {
+                            *tag_value = resolved;
+                        }
+                    }
+                }
+            }
+            session
+        })
+        .collect();
 
-    // Remove the tag with the specified ID
-    tags.retain(|t| t.id != tag_id);
+    let export = DayExport {
+        note: session.as_ref().and_then(|s| s.notes.clone()),
+        date,
+        session,
+        manual_sessions,
+    };
 
-    // Save the updated tags back
-    save_tags(tags, app).await
+    let json = serde_json::to_string_pretty(&export)
+        .map_err(|e| format!("Failed to serialize day export: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write day export to {}: {}", path, e))?;
+
+    Ok(())
+}
+
+// Everything printed in the monthly PDF report
+struct MonthlyReport {
+    title: String,
+    days: Vec<(chrono::NaiveDate, u32, u32)>, // date, focus minutes, sessions
+    tags: Vec<(String, u64)>,                 // tag name, minutes; largest first
+}
+
+fn format_minutes(minutes: u64) -> String {
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+// Lay the report out on A4 pages: totals, a per-day table and the tag
+// breakdown, starting a second page only if the tags don't fit on the first
+fn render_monthly_report_pdf(report: &MonthlyReport) -> Result<Vec<u8>, String> {
+    use printpdf::{BuiltinFont, Mm, OffsetDateTime, PdfDocument};
+
+    const PAGE_WIDTH: f32 = 210.0;
+    const PAGE_HEIGHT: f32 = 297.0;
+    const MARGIN: f32 = 20.0;
+    const LINE_HEIGHT: f32 = 5.5;
+    const MAX_TAG_ROWS: usize = 25; // keeps the report to two pages
+
+    let (doc, page, layer) =
+        PdfDocument::new(&report.title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Report");
+    // Date the file by the month it reports on instead of the time of export,
+    // so the metadata doesn't change when the same month is exported again
+    let month_start = report
+        .days
+        .first()
+        .map(|(date, _, _)| date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp())
+        .unwrap_or(0);
+    let month_start = OffsetDateTime::from_unix_timestamp(month_start)
+        .map_err(|e| format!("Invalid report date: {}", e))?;
+    let doc = doc
+        .with_creation_date(month_start)
+        .with_mod_date(month_start)
+        .with_metadata_date(month_start);
+    let font = doc
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(|e| format!("Failed to add font: {}", e))?;
+    let bold = doc
+        .add_builtin_font(BuiltinFont::HelveticaBold)
+        .map_err(|e| format!("Failed to add font: {}", e))?;
+
+    // Move down by `height` and return the layer and baseline to write at,
+    // continuing on a new page at the bottom margin
+    let mut layer = doc.get_page(page).get_layer(layer);
+    let mut y = PAGE_HEIGHT - MARGIN;
+    let mut next_line = |height: f32| {
+        y -= height;
+        if y < MARGIN {
+            let (page, new_layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Report");
+            layer = doc.get_page(page).get_layer(new_layer);
+            y = PAGE_HEIGHT - MARGIN - height;
+        }
+        (layer.clone(), Mm(y))
+    };
+
+    let total_minutes: u64 = report.days.iter().map(|(_, m, _)| *m as u64).sum();
+    let total_sessions: u32 = report.days.iter().map(|(_, _, s)| s).sum();
+    let active_days = report.days.iter().filter(|(_, m, _)| *m > 0).count() as u64;
+    let best_day = report
+        .days
+        .iter()
+        .filter(|(_, m, _)| *m > 0)
+        .max_by_key(|(_, m, _)| *m);
+
+    let (layer, y_title) = next_line(0.0);
+    layer.use_text(&report.title, 18.0, Mm(MARGIN), y_title, &bold);
+    let summary = [
+        format!("Total focus: {}", format_minutes(total_minutes)),
+        format!("Sessions: {}", total_sessions),
+        format!("Active days: {} of {}", active_days, report.days.len()),
+        format!(
+            "Average per active day: {}",
+            format_minutes(total_minutes.checked_div(active_days).unwrap_or(0))
+        ),
+        match best_day {
+            Some((date, minutes, _)) => format!(
+                "Best day: {} ({})",
+                format_session_date(*date),
+                format_minutes(*minutes as u64)
+            ),
+            None => "Best day: -".to_string(),
+        },
+    ];
+    let mut height = 2.0 * LINE_HEIGHT;
+    for line in summary {
+        let (layer, y_line) = next_line(height);
+        layer.use_text(line, 11.0, Mm(MARGIN), y_line, &font);
+        height = LINE_HEIGHT;
+    }
+
+    let (layer, y_header) = next_line(2.0 * LINE_HEIGHT);
+    layer.use_text("Date", 11.0, Mm(MARGIN), y_header, &bold);
+    layer.use_text("Focus", 11.0, Mm(90.0), y_header, &bold);
+    layer.use_text("Sessions", 11.0, Mm(130.0), y_header, &bold);
+    for (date, minutes, sessions) in &report.days {
+        let (layer, y_row) = next_line(LINE_HEIGHT);
+        layer.use_text(format_session_date(*date), 10.0, Mm(MARGIN), y_row, &font);
+        layer.use_text(
+            format_minutes(*minutes as u64),
+            10.0,
+            Mm(90.0),
+            y_row,
+            &font,
+        );
+        layer.use_text(sessions.to_string(), 10.0, Mm(130.0), y_row, &font);
+    }
+
+    let (layer, y_header) = next_line(2.0 * LINE_HEIGHT);
+    layer.use_text("Tag", 11.0, Mm(MARGIN), y_header, &bold);
+    layer.use_text("Time", 11.0, Mm(90.0), y_header, &bold);
+    if report.tags.is_empty() {
+        let (layer, y_row) = next_line(LINE_HEIGHT);
+        layer.use_text("No tagged sessions", 10.0, Mm(MARGIN), y_row, &font);
+    }
+    for (name, minutes) in report.tags.iter().take(MAX_TAG_ROWS) {
+        let (layer, y_row) = next_line(LINE_HEIGHT);
+        layer.use_text(name, 10.0, Mm(MARGIN), y_row, &font);
+        layer.use_text(format_minutes(*minutes), 10.0, Mm(90.0), y_row, &font);
+    }
+
+    doc.save_to_bytes()
+        .map_err(|e| format!("Failed to render PDF: {}", e))
 }
 
 #[tauri::command]
-async fn load_session_tags(app: AppHandle) -> Result<Vec<SessionTag>, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+async fn export_monthly_report_pdf(
+    year: u32,
+    month: u32,
+    path: String,
+    app: AppHandle,
+) -> Result<(), String> {
+    let first_day = chrono::NaiveDate::from_ymd_opt(year as i32, month, 1)
+        .ok_or_else(|| format!("Invalid month {}-{}", year, month))?;
+    let next_month = first_day
+        .checked_add_months(chrono::Months::new(1))
+        .ok_or_else(|| format!("Invalid month {}-{}", year, month))?;
+    let in_month = |date: chrono::NaiveDate| date >= first_day && date < next_month;
+
+    let manual_sessions = load_manual_sessions(app.clone()).await?;
+    let session_tags = load_session_tags(app.clone()).await?;
+    let tags = load_tags(app).await?;
+
+    let daily_minutes = daily_focus_minutes(&manual_sessions);
+    let daily_sessions = daily_focus_sessions(&manual_sessions);
+
+    let days = first_day
+        .iter_days()
+        .take_while(|date| *date < next_month)
+        .map(|date| {
+            (
+                date,
+                daily_minutes.get(&date).copied().unwrap_or(0),
+                daily_sessions.get(&date).copied().unwrap_or(0),
+            )
+        })
+        .collect();
+
+    let mut tag_seconds: HashMap<&str, u64> = HashMap::new();
+    for session_tag in &session_tags {
+        if parse_iso_local_date(&session_tag.created_at).is_some_and(in_month) {
+            *tag_seconds.entry(session_tag.tag_id.as_str()).or_default() +=
+                session_tag.duration as u64;
+        }
+    }
+    let mut tag_minutes: Vec<(String, u64)> = tag_seconds
+        .into_iter()
+        .map(|(tag_id, seconds)| {
+            let name = tags
+                .iter()
+                .find(|t| t.id == tag_id)
+                .map(|t| t.name.clone())
+                .unwrap_or_else(|| tag_id.to_string());
+            (name, seconds / 60)
+        })
+        .collect();
+    tag_minutes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let report = MonthlyReport {
+        title: format!("Presto report {}", first_day.format("%B %Y")),
+        days,
+        tags: tag_minutes,
+    };
+    let pdf = render_monthly_report_pdf(&report)?;
+    fs::write(&path, pdf).map_err(|e| format!("Failed to write PDF report to {}: {}", path, e))?;
 
-    let file_path = app_data_dir.join("session_tags.json");
+    Ok(())
+}
 
-    if file_path.exists() {
-        let content = fs::read_to_string(&file_path)
-            .map_err(|e| format!("Failed to read session tags: {}", e))?;
-        Ok(serde_json::from_str(&content).unwrap_or_else(|_| Vec::new()))
-    } else {
-        Ok(Vec::new())
+// Serialize a record as one JSON line, tagged with its record_type
+fn to_jsonl_line<T: Serialize>(record: &T, record_type: &str) -> Result<String, String> {
+    let mut value = serde_json::to_value(record)
+        .map_err(|e| format!("Failed to serialize {}: {}", record_type, e))?;
+    if let serde_json::Value::Object(ref mut map) = value {
+        map.insert(
+            "record_type".to_string(),
+            serde_json::Value::String(record_type.to_string()),
+        );
     }
+    serde_json::to_string(&value).map_err(|e| format!("Failed to serialize {}: {}", record_type, e))
 }
 
 #[tauri::command]
-async fn save_session_tags(session_tags: Vec<SessionTag>, app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
-
-    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+async fn export_events_jsonl(path: String, app: AppHandle) -> Result<(), String> {
+    let history = get_stats_history(app.clone()).await?;
+    let manual_sessions = load_manual_sessions(app).await?;
+
+    let mut content = String::new();
+    for stat in &history {
+        content.push_str(&to_jsonl_line(stat, "daily_stat")?);
+        content.push('\n');
+    }
+    for session in &manual_sessions {
+        content.push_str(&to_jsonl_line(session, "manual_session")?);
+        content.push('\n');
+    }
 
-    let file_path = app_data_dir.join("session_tags.json");
-    let json = serde_json::to_string_pretty(&session_tags)
-        .map_err(|e| format!("Failed to serialize session tags: {}", e))?;
-    fs::write(file_path, json).map_err(|e| format!("Failed to write session tags file: {}", e))?;
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write JSON Lines file to {}: {}", path, e))?;
 
     Ok(())
 }
 
-#[tauri::command]
-async fn add_session_tag(session_tag: SessionTag, app: AppHandle) -> Result<(), String> {
-    let mut session_tags = load_session_tags(app.clone()).await?;
-    session_tags.push(session_tag);
-    save_session_tags(session_tags, app).await
+// Millisecond timestamp plus a counter, similar to the ids the frontend generates
+fn generate_id() -> String {
+    let millis = chrono::Local::now().timestamp_millis();
+    let counter = ID_COUNTER.fetch_add(1, Ordering::SeqCst);
+    format!("{}{:x}", millis, counter)
+}
+
+// Split CSV content into records, honouring quoted fields (which may contain
+// commas, escaped quotes and newlines). Each record carries its starting line.
+fn parse_csv_records(content: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                fields.push(std::mem::take(&mut field));
+                if fields.iter().any(|f| !f.is_empty()) {
+                    records.push((record_line, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                record_line = line;
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    fields.push(field);
+    if fields.iter().any(|f| !f.is_empty()) {
+        records.push((record_line, fields));
+    }
+
+    records
 }
 
 #[tauri::command]
-async fn update_tray_menu(
-    app: AppHandle,
-    is_running: bool,
-    is_paused: bool,
-    current_mode: String,
-    completed_sessions: Option<i32>,
-    total_sessions: Option<i32>,
-) -> Result<(), String> {
-    let tray = app.tray_by_id("main");
+async fn import_manual_sessions_csv(path: String, app: AppHandle) -> Result<ImportSummary, String> {
+    ensure_writable()?;
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read CSV file {}: {}", path, e))?;
+
+    let mut records = parse_csv_records(&content).into_iter();
+    let (_, header) = records
+        .next()
+        .ok_or_else(|| "CSV file is empty".to_string())?;
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+
+    let (Some(date_col), Some(start_col), Some(end_col)) =
+        (column("date"), column("start_time"), column("end_time"))
+    else {
+        return Err("CSV must have date, start_time and end_time columns".to_string());
+    };
+    let duration_col = column("duration");
+    let type_col = column("session_type");
+    let notes_col = column("notes");
+    let tags_col = column("tags");
 
-    if let Some(tray) = tray {
-        // Create session progress text if available
-        let session_progress = if let (Some(completed), Some(total)) = (completed_sessions, total_sessions) {
-            format!(" ({}/{})", completed, total)
-        } else {
-            String::new()
-        };
-        
-        let show_item = MenuItem::with_id(&app, "show", &format!("Show Presto{}", session_progress), true, None::<&str>)
-            .map_err(|e| format!("Failed to create show item: {}", e))?;
+    let _guard = DATA_LOCK.lock().await;
 
-        // Start Session: enabled only if not running
-        let start_session_item = MenuItem::with_id(
-            &app,
-            "start_session",
-            "Start Session",
-            !is_running,
-            None::<&str>,
-        )
-        .map_err(|e| format!("Failed to create start session item: {}", e))?;
+    let mut tags = load_tags(app.clone()).await?;
+    let mut sessions = load_manual_sessions(app.clone()).await?;
+    let mut tag_links = load_session_tags(app.clone()).await?;
+    let mut summary = ImportSummary {
+        imported: 0,
+        created_tags: 0,
+        skipped: Vec::new(),
+    };
 
-        // Pause: enabled only if running and not paused
-        let pause_item = MenuItem::with_id(
-            &app,
-            "pause",
-            "Pause",
-            is_running && !is_paused,
-            None::<&str>,
-        )
-        .map_err(|e| format!("Failed to create pause item: {}", e))?;
+    for (line, row) in records {
+        let get = |index: Option<usize>| {
+            index
+                .and_then(|i| row.get(i))
+                .map(|v| v.trim().to_string())
+                .unwrap_or_default()
+        };
+        let mut skip = |reason: String| summary.skipped.push(SkippedRow { line, reason });
 
-        // Skip: enabled only if running
-        let skip_item = MenuItem::with_id(&app, "skip", "Skip Session", is_running, None::<&str>)
-            .map_err(|e| format!("Failed to create skip item: {}", e))?;
+        let date = match parse_date_arg(&get(Some(date_col))) {
+            Ok(date) => date,
+            Err(e) => {
+                skip(e);
+                continue;
+            }
+        };
 
-        // Cancel: enabled if in focus mode, disabled in break/longBreak (undo)
-        let cancel_text = if current_mode == "focus" {
-            "Cancel"
-        } else {
-            "Cancel Last"
+        let start_time = get(Some(start_col));
+        let end_time = get(Some(end_col));
+        let parse_time = |time: &str| chrono::NaiveTime::parse_from_str(time, "%H:%M").ok();
+        let (Some(start), Some(end)) = (parse_time(&start_time), parse_time(&end_time)) else {
+            skip(format!(
+                "Invalid start/end time '{}' - '{}'",
+                start_time, end_time
+            ));
+            continue;
         };
-        let cancel_item = MenuItem::with_id(&app, "cancel", cancel_text, true, None::<&str>)
-            .map_err(|e| format!("Failed to create cancel item: {}", e))?;
 
-        let quit_item = MenuItem::with_id(&app, "quit", "Quit", true, None::<&str>)
-            .map_err(|e| format!("Failed to create quit item: {}", e))?;
+        // Fall back to the time range when no duration is given
+        let duration_field = get(duration_col);
+        let duration = if duration_field.is_empty() {
+            let minutes = (end - start).num_minutes();
+            (if minutes < 0 {
+                minutes + 24 * 60
+            } else {
+                minutes
+            }) as u32
+        } else {
+            match duration_field.parse::<u32>() {
+                Ok(duration) => duration,
+                Err(_) => {
+                    skip(format!("Invalid duration '{}'", duration_field));
+                    continue;
+                }
+            }
+        };
 
-        let new_menu = Menu::with_items(
-            &app,
-            &[
-                &show_item,
-                &start_session_item,
-                &pause_item,
-                &skip_item,
-                &cancel_item,
-                &quit_item,
-            ],
-        )
-        .map_err(|e| format!("Failed to create menu: {}", e))?;
+        let session_type = match get(type_col).as_str() {
+            "" => "focus".to_string(),
+            t @ ("focus" | "break" | "longBreak" | "custom") => t.to_string(),
+            other => {
+                skip(format!("Unknown session type '{}'", other));
+                continue;
+            }
+        };
 
-        tray.set_menu(Some(new_menu))
-            .map_err(|e| format!("Failed to set tray menu: {}", e))?;
-    }
+        // Resolve tags by name (case-insensitive), creating any that don't exist yet
+        let mut session_tags = Vec::new();
+        let mut tag_ids = Vec::new();
+        for name in get(tags_col)
+            .split(';')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+        {
+            let tag = match tags.iter().find(|t| t.name.eq_ignore_ascii_case(name)) {
+                Some(tag) => tag.clone(),
+                None => {
+                    let tag = Tag {
+                        id: format!("tag-{}", generate_id()),
+                        name: name.to_string(),
+                        icon: "ri-price-tag-3-line".to_string(),
+                        color: "#4CAF50".to_string(),
+                        created_at: chrono::Local::now().to_rfc3339(),
+                        pinned: None,
+                    };
+                    tags.push(tag.clone());
+                    summary.created_tags += 1;
+                    tag
+                }
+            };
+            session_tags.push(
+                serde_json::to_value(&tag)
+                    .map_err(|e| format!("Failed to serialize tag: {}", e))?,
+            );
+            tag_ids.push(tag.id);
+        }
 
-    Ok(())
-}
+        // Dated at the session's start so per-period tag totals pick it up
+        let created_at = date
+            .and_time(start)
+            .and_local_timezone(chrono::Local)
+            .earliest()
+            .unwrap_or_else(chrono::Local::now)
+            .to_rfc3339();
+
+        let session_id = generate_id();
+        for tag_id in tag_ids {
+            tag_links.push(SessionTag {
+                session_id: session_id.clone(),
+                tag_id,
+                duration: duration * 60,
+                created_at: created_at.clone(),
+            });
+        }
 
-#[tauri::command]
-async fn write_excel_file(path: String, data: String) -> Result<(), String> {
-    // Decode base64 data
-    let decoded_data = general_purpose::STANDARD
-        .decode(data)
-        .map_err(|e| format!("Failed to decode base64 data: {}", e))?;
+        let notes = get(notes_col);
+        sessions.push(ManualSession {
+            id: session_id,
+            session_type,
+            duration,
+            start_time,
+            end_time,
+            notes: if notes.is_empty() { None } else { Some(notes) },
+            created_at,
+            date: format_session_date(date),
+            tags: Some(session_tags),
+        });
+        summary.imported += 1;
+    }
 
-    // Write the binary data to file
-    fs::write(&path, decoded_data)
-        .map_err(|e| format!("Failed to write Excel file to {}: {}", path, e))?;
+    if summary.created_tags > 0 {
+        write_tags(tags, app.clone()).await?;
+    }
+    if summary.imported > 0 {
+        // Imported history is taken as is, however long its sessions are
+        write_manual_sessions(sessions, Some(true), app.clone()).await?;
+        write_session_tags(tag_links, app).await?;
+    }
 
-    Ok(())
+    Ok(summary)
 }
 
 #[tauri::command]
 async fn start_oauth_server(window: tauri::Window) -> Result<u16, String> {
-    start(move |url| {
-        println!("OAuth callback received: {}", url);
+    // A retried flow replaces the previous server instead of leaking its port
+    stop_oauth_server().await?;
+
+    let generation = OAUTH_GENERATION.load(Ordering::SeqCst);
+    let port = start(move |url| {
+        if OAUTH_GENERATION.load(Ordering::SeqCst) != generation {
+            log::warn!("Ignoring OAuth callback from a stopped server");
+            return;
+        }
+        log::info!("OAuth callback received: {}", url);
         // Emit the URL to the frontend
         let _ = window.emit("oauth-callback", url);
     })
-    .map_err(|err| err.to_string())
+    .map_err(|err| err.to_string())?;
+
+    *OAUTH_PORT.lock().unwrap() = Some(port);
+    Ok(port)
+}
+
+#[tauri::command]
+async fn stop_oauth_server() -> Result<(), String> {
+    OAUTH_GENERATION.fetch_add(1, Ordering::SeqCst);
+
+    let port = OAUTH_PORT.lock().unwrap().take();
+    if let Some(port) = port {
+        cancel(port).map_err(|e| format!("Failed to stop OAuth server on port {}: {}", port, e))?;
+        log::info!("Stopped OAuth server on port {}", port);
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -1415,6 +7382,256 @@ fn set_dock_visibility_native(visible: bool) {
     }
 }
 
+// Hide or restore the dock icon (macOS) or taskbar entry (Windows/Linux),
+// independently of the window being shown
+fn apply_menubar_only(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    MENUBAR_ONLY.store(enabled, Ordering::SeqCst);
+
+    #[cfg(target_os = "macos")]
+    {
+        app.run_on_main_thread(move || set_dock_visibility_native(!enabled))
+            .map_err(|e| format!("Failed to run on main thread: {}", e))?;
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    {
+        if let Some(window) = app.get_webview_window("main") {
+            set_taskbar_visibility(&window, !enabled);
+        }
+    }
+
+    Ok(())
+}
+
+// Toggle menu-bar-only mode for this run; start_hidden_from_dock decides the
+// mode at launch
+#[tauri::command]
+async fn set_menubar_only(enabled: bool, app: AppHandle) -> Result<(), String> {
+    apply_menubar_only(&app, enabled)
+}
+
+// Windows/Linux counterpart of hiding the dock icon: drop the taskbar entry
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn set_taskbar_visibility(window: &tauri::WebviewWindow, visible: bool) {
+    let _ = window.set_skip_taskbar(!visible);
+}
+
+// Battery level from IOKit's AppleSmartBattery service. CurrentCapacity is
+// already a percentage on Apple Silicon and in mAh on Intel, so it's always
+// divided by MaxCapacity.
+#[cfg(target_os = "macos")]
+fn read_battery_status() -> Option<BatteryStatus> {
+    use core_foundation::base::{kCFAllocatorDefault, CFAllocatorRef, CFType, CFTypeRef, TCFType};
+    use core_foundation::boolean::CFBoolean;
+    use core_foundation::number::CFNumber;
+    use core_foundation::string::{CFString, CFStringRef};
+    use libc::{c_char, c_void};
+
+    type IoObject = u32;
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOServiceMatching(name: *const c_char) -> *mut c_void;
+        fn IOServiceGetMatchingService(main_port: u32, matching: *mut c_void) -> IoObject;
+        fn IORegistryEntryCreateCFProperty(
+            entry: IoObject,
+            key: CFStringRef,
+            allocator: CFAllocatorRef,
+            options: u32,
+        ) -> CFTypeRef;
+        fn IOObjectRelease(object: IoObject) -> i32;
+    }
+
+    // Port 0 selects the default main port; the matching dictionary is consumed
+    let service =
+        unsafe { IOServiceGetMatchingService(0, IOServiceMatching(c"AppleSmartBattery".as_ptr())) };
+    if service == 0 {
+        return None; // no battery, e.g. a desktop Mac
+    }
+
+    let property = |key: &str| -> Option<CFType> {
+        let key = CFString::new(key);
+        let value = unsafe {
+            IORegistryEntryCreateCFProperty(
+                service,
+                key.as_concrete_TypeRef(),
+                kCFAllocatorDefault,
+                0,
+            )
+        };
+        (!value.is_null()).then(|| unsafe { CFType::wrap_under_create_rule(value) })
+    };
+    let number = |key: &str| property(key)?.downcast::<CFNumber>()?.to_i64();
+
+    let status = (|| {
+        let current = number("CurrentCapacity")?;
+        let max = number("MaxCapacity").filter(|max| *max > 0)?;
+        let charging = property("IsCharging")
+            .and_then(|value| value.downcast::<CFBoolean>())
+            .map(bool::from)
+            .unwrap_or(false);
+        Some(BatteryStatus {
+            percent: (current * 100 / max).clamp(0, 100) as u8,
+            charging,
+        })
+    })();
+
+    unsafe {
+        IOObjectRelease(service);
+    }
+    status
+}
+
+#[cfg(target_os = "windows")]
+fn read_battery_status() -> Option<BatteryStatus> {
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+
+    const BATTERY_FLAG_CHARGING: u8 = 8;
+    const BATTERY_FLAG_NO_BATTERY: u8 = 128;
+
+    let mut status = SystemPowerStatus::default();
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    // 255 = unknown percentage
+    if status.battery_flag & BATTERY_FLAG_NO_BATTERY != 0 || status.battery_life_percent > 100 {
+        return None;
+    }
+
+    Some(BatteryStatus {
+        percent: status.battery_life_percent,
+        charging: status.battery_flag & BATTERY_FLAG_CHARGING != 0,
+    })
+}
+
+// First battery listed under /sys/class/power_supply
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn read_battery_status() -> Option<BatteryStatus> {
+    let read = |path: PathBuf| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+
+    fs::read_dir("/sys/class/power_supply")
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|supply| read(supply.join("type")).as_deref() == Some("Battery"))
+        .find_map(|supply| {
+            let percent: u32 = read(supply.join("capacity"))?.parse().ok()?;
+            Some(BatteryStatus {
+                percent: percent.min(100) as u8,
+                charging: read(supply.join("status")).as_deref() == Some("Charging"),
+            })
+        })
+}
+
+#[tauri::command]
+async fn get_battery_status() -> Result<BatteryStatus, String> {
+    read_battery_status().ok_or_else(|| "No battery found".to_string())
+}
+
+// Poll the battery and emit "low-battery" once each time it discharges below
+// low_battery_threshold, so the frontend can offer to pause or save the session
+fn start_battery_monitor(app: AppHandle) {
+    thread::spawn(move || {
+        let mut warned = false;
+        loop {
+            if let Some(status) = read_battery_status() {
+                let threshold = tauri::async_runtime::block_on(load_settings(app.clone()))
+                    .map(|settings| settings.notifications.low_battery_threshold)
+                    .unwrap_or_else(|_| default_low_battery_threshold());
+                let low = !status.charging && (status.percent as u32) < threshold;
+
+                if low && !warned {
+                    log::info!("Battery low: {}%", status.percent);
+                    let _ = app.emit("low-battery", &status);
+                }
+                warned = low;
+            }
+
+            thread::sleep(BATTERY_POLL_INTERVAL);
+        }
+    });
+}
+
+// Screen lock detection on macOS
+//
+// The loginwindow posts com.apple.screenIsLocked / com.apple.screenIsUnlocked as
+// distributed notifications. We register a tiny Objective-C observer class for
+// them and forward each one to the frontend as a "screen-locked"/"screen-unlocked"
+// event. The payload tells the frontend whether lock_pauses_timer is enabled.
+#[cfg(target_os = "macos")]
+fn emit_screen_lock_event(event: &str) {
+    if let Some(app) = SCREEN_LOCK_APP_HANDLE.get() {
+        let _ = app.emit(event, LOCK_PAUSES_TIMER.load(Ordering::SeqCst));
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn register_screen_lock_observer(app: AppHandle) {
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::NSString;
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Object, Sel};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    extern "C" fn screen_locked(_this: &Object, _cmd: Sel, _notification: id) {
+        emit_screen_lock_event("screen-locked");
+    }
+
+    extern "C" fn screen_unlocked(_this: &Object, _cmd: Sel, _notification: id) {
+        emit_screen_lock_event("screen-unlocked");
+    }
+
+    // Only ever register once
+    if SCREEN_LOCK_APP_HANDLE.set(app).is_err() {
+        return;
+    }
+
+    unsafe {
+        let Some(mut decl) = ClassDecl::new("PrestoScreenLockObserver", class!(NSObject)) else {
+            return;
+        };
+        decl.add_method(
+            sel!(screenLocked:),
+            screen_locked as extern "C" fn(&Object, Sel, id),
+        );
+        decl.add_method(
+            sel!(screenUnlocked:),
+            screen_unlocked as extern "C" fn(&Object, Sel, id),
+        );
+        let observer_class = decl.register();
+
+        // The observer lives for the whole app lifetime, so it is never released
+        let observer: id = msg_send![observer_class, new];
+        let center: id = msg_send![class!(NSDistributedNotificationCenter), defaultCenter];
+
+        let locked_name = NSString::alloc(nil).init_str("com.apple.screenIsLocked");
+        let unlocked_name = NSString::alloc(nil).init_str("com.apple.screenIsUnlocked");
+
+        let _: () = msg_send![center, addObserver: observer
+                                      selector: sel!(screenLocked:)
+                                      name: locked_name
+                                      object: nil];
+        let _: () = msg_send![center, addObserver: observer
+                                      selector: sel!(screenUnlocked:)
+                                      name: unlocked_name
+                                      object: nil];
+    }
+}
+
 // Status bar visibility management using Carbon APIs
 //
 // Implementation Notes:
@@ -1438,14 +7655,14 @@ async fn set_status_bar_visibility(_app: AppHandle, visible: bool) -> Result<(),
     {
         match set_system_ui_mode_safe(visible) {
             Ok(_) => {
-                println!(
+                log::info!(
                     "✅ Status bar visibility successfully set to: {}",
                     if visible { "visible" } else { "hidden" }
                 );
                 Ok(())
             }
             Err(e) => {
-                eprintln!("❌ Failed to set status bar visibility: {}", e);
+                log::error!("❌ Failed to set status bar visibility: {}", e);
                 Err(format!("Failed to set status bar visibility: {}", e))
             }
         }
@@ -1497,7 +7714,7 @@ fn set_system_ui_mode_safe(visible: bool) -> Result<(), String> {
 
         let options: SystemUIOptions = 0; // No special options
 
-        println!(
+        log::debug!(
             "🔧 Carbon API: Setting SystemUIMode to {} ({})",
             mode,
             if visible {
@@ -1511,7 +7728,7 @@ fn set_system_ui_mode_safe(visible: bool) -> Result<(), String> {
         let result: OSStatus = SetSystemUIMode(mode, options);
 
         if result == NO_ERR {
-            println!("✅ Carbon API: SetSystemUIMode succeeded");
+            log::debug!("✅ Carbon API: SetSystemUIMode succeeded");
             Ok(())
         } else {
             let error_msg = format!(
@@ -1519,7 +7736,7 @@ fn set_system_ui_mode_safe(visible: bool) -> Result<(), String> {
                 result,
                 get_osstatus_description(result)
             );
-            eprintln!("❌ Carbon API: {}", error_msg);
+            log::error!("❌ Carbon API: {}", error_msg);
             Err((result, error_msg))
         }
     };
@@ -1532,11 +7749,11 @@ fn set_system_ui_mode_safe(visible: bool) -> Result<(), String> {
     // If primary approach failed, try fallback methods
     let (status_code, error_msg) = primary_result.unwrap_err();
 
-    eprintln!("🔄 Primary method failed, attempting fallback approaches...");
+    log::warn!("🔄 Primary method failed, attempting fallback approaches...");
 
     // Fallback 1: Try with a small delay and retry
     if status_code == PARAM_ERR || status_code == MEM_FULL_ERR {
-        println!("🔄 Fallback 1: Retrying after brief delay...");
+        log::info!("🔄 Fallback 1: Retrying after brief delay...");
         thread::sleep(Duration::from_millis(100));
 
         let retry_result = unsafe {
@@ -1548,7 +7765,7 @@ fn set_system_ui_mode_safe(visible: bool) -> Result<(), String> {
             let result: OSStatus = SetSystemUIMode(mode, 0);
 
             if result == NO_ERR {
-                println!("✅ Fallback 1: Retry succeeded");
+                log::info!("✅ Fallback 1: Retry succeeded");
                 Ok(())
             } else {
                 Err(format!("Retry failed with OSStatus: {}", result))
@@ -1562,7 +7779,7 @@ fn set_system_ui_mode_safe(visible: bool) -> Result<(), String> {
 
     // Fallback 2: For hiding, try a more conservative approach
     if !visible {
-        println!("🔄 Fallback 2: Trying conservative hide approach...");
+        log::info!("🔄 Fallback 2: Trying conservative hide approach...");
 
         let conservative_result = unsafe {
             // Try normal mode first, then content suppressed
@@ -1571,7 +7788,7 @@ fn set_system_ui_mode_safe(visible: bool) -> Result<(), String> {
             let result: OSStatus = SetSystemUIMode(K_UI_MODE_CONTENT_SUPPRESSED, 0);
 
             if result == NO_ERR {
-                println!("✅ Fallback 2: Conservative approach succeeded");
+                log::info!("✅ Fallback 2: Conservative approach succeeded");
                 Ok(())
             } else {
                 Err(format!(
@@ -1594,7 +7811,7 @@ fn set_system_ui_mode_safe(visible: bool) -> Result<(), String> {
         error_msg
     );
 
-    eprintln!("❌ {}", detailed_error);
+    log::error!("❌ {}", detailed_error);
     Err(detailed_error)
 }
 
@@ -1617,3 +7834,128 @@ fn get_osstatus_description(status: libc::c_int) -> &'static str {
         _ => "Unknown error - Undocumented error code",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> chrono::NaiveDate {
+        parse_date_arg(date).unwrap()
+    }
+
+    #[test]
+    fn streak_survives_spring_forward() {
+        // Clocks went forward on Mar 31 2024 in Europe and Mar 10 2024 in the
+        // US; the 23-hour days must still link their neighbours
+        for days in [
+            ["Sat Mar 30 2024", "Sun Mar 31 2024", "Mon Apr 01 2024"],
+            ["Sat Mar 09 2024", "Sun Mar 10 2024", "Mon Mar 11 2024"],
+        ] {
+            let active: BTreeSet<chrono::NaiveDate> = days.iter().map(|d| date(d)).collect();
+            assert_eq!(compute_streaks(&active, date(days[2])), (3, 3));
+        }
+    }
+
+    #[test]
+    fn consistency_is_full_for_equal_days() {
+        assert_eq!(consistency_score(&[45; 7]), 100.0);
+    }
+
+    #[test]
+    fn consistency_is_low_for_a_single_big_day() {
+        assert!(consistency_score(&[0, 0, 0, 0, 0, 0, 240]) < 10.0);
+    }
+
+    #[test]
+    fn focus_score_is_full_when_every_target_is_met() {
+        assert_eq!(focus_score(8, 8, 200, 200, 1.0), 100.0);
+        // Going past a target doesn't earn extra, and no target counts as met
+        assert_eq!(focus_score(12, 8, 400, 200, 1.5), 100.0);
+        assert_eq!(focus_score(0, 0, 0, 0, 1.0), 100.0);
+    }
+
+    #[test]
+    fn focus_score_is_zero_without_activity() {
+        assert_eq!(focus_score(0, 8, 0, 200, 0.0), 0.0);
+        assert_eq!(focus_score(0, 8, 0, 200, -0.5), 0.0);
+    }
+
+    #[test]
+    fn focus_grade_boundaries_are_inclusive() {
+        let grades: Vec<&str> = [100.0, 90.0, 89.9, 80.0, 70.0, 60.0, 59.9, 0.0]
+            .into_iter()
+            .map(focus_grade)
+            .collect();
+        assert_eq!(grades, ["A", "A", "B", "B", "C", "D", "F", "F"]);
+    }
+
+    #[test]
+    fn session_load_grows_slower_than_minutes() {
+        assert_eq!(session_load(0.0), 0.0);
+        assert_eq!(session_load(-10.0), 0.0);
+        assert!((session_load(25.0) - 25.0 * 2f64.ln()).abs() < 1e-9);
+        // One long block counts for less than the same time in two halves
+        assert!(session_load(100.0) < 2.0 * session_load(50.0));
+        assert!(session_load(100.0) > session_load(50.0));
+    }
+
+    #[test]
+    fn monthly_report_moves_tags_to_a_second_page() {
+        let report = MonthlyReport {
+            title: "Presto - June 2024".to_string(),
+            days: date("Sat Jun 01 2024")
+                .iter_days()
+                .take(30)
+                .map(|day| (day, 50, 2))
+                .collect(),
+            tags: (1..=20).map(|i| (format!("Tag {}", i), 60)).collect(),
+        };
+
+        let bytes = render_monthly_report_pdf(&report).unwrap();
+        let pdf = printpdf::lopdf::Document::load_mem(&bytes).unwrap();
+        assert_eq!(pdf.get_pages().len(), 2);
+        assert!(pdf
+            .extract_text(&[1])
+            .unwrap()
+            .contains("Total focus: 25h 00m"));
+        assert!(pdf.extract_text(&[2]).unwrap().contains("Tag 20"));
+    }
+
+    #[test]
+    fn float_rounding_is_not_a_missing_key() {
+        let raw = serde_json::json!({ "audio": { "tick_volume": 0.3 }, "tags": [{ "id": "a" }] });
+        let normalized = serde_json::json!({
+            "audio": { "tick_volume": 0.30000001192092896 },
+            "tags": [{ "id": "a", "pinned": null }]
+        });
+        assert!(!has_missing_keys(&normalized, &raw));
+
+        let filled = serde_json::json!({
+            "audio": { "tick_volume": 0.3, "tick_interval": 1 },
+            "tags": [{ "id": "a" }]
+        });
+        assert!(has_missing_keys(&filled, &raw));
+    }
+
+    #[test]
+    fn week_of_a_sunday_depends_on_the_week_start() {
+        let sunday = date("Sun Jun 09 2024");
+        assert_eq!(
+            start_of_week(sunday, chrono::Weekday::Mon),
+            date("Mon Jun 03 2024")
+        );
+        assert_eq!(start_of_week(sunday, chrono::Weekday::Sun), sunday);
+
+        assert_eq!(week_info(sunday, chrono::Weekday::Mon).iso_week, 23);
+        assert_eq!(week_info(sunday, chrono::Weekday::Sun).iso_week, 24);
+    }
+
+    #[test]
+    fn sunday_week_takes_the_iso_year_of_its_monday() {
+        // Mon Dec 30 2024 is in ISO week 1 of 2025
+        let week = week_info(date("Sun Dec 29 2024"), chrono::Weekday::Sun);
+        assert_eq!((week.iso_week, week.year), (1, 2025));
+        assert_eq!(week.start_date, "Sun Dec 29 2024");
+        assert_eq!(week.end_date, "Sat Jan 04 2025");
+    }
+}
@@ -10,6 +10,7 @@ export class PomodoroTimer {
         this.currentMode = 'focus'; // 'focus', 'break', 'longBreak'
         this.timeRemaining = 25 * 60; // 25 minutes in seconds
         this.timerInterval = null;
+        this.isTicking = false; // Whether the backend tick sound is running
 
         // Smart pause states
         this.smartPauseEnabled = false;
@@ -28,6 +29,7 @@ export class PomodoroTimer {
         // Session time tracking
         this.sessionStartTime = null; // When the current session was started
         this.lastSessionStartTime = null; // Preserved start time for the last completed session
+        this.pendingManualSession = null; // Finished focus session waiting to be saved with the day's totals
         this.currentSessionElapsedTime = 0; // Actual elapsed time for current session (in seconds)
         this.lastCompletedSessionTime = 0; // Time of the last completed session for undo functionality
         this.sessionCompletedButNotSaved = false; // Flag to track if session completed but not saved yet
@@ -360,6 +362,11 @@ export class PomodoroTimer {
                 this.undoLastSession();
             }
         });
+
+        // Listen for time added/removed via the extend_timer command (payload in seconds)
+        await listen('timer-extend', (event) => {
+            this.adjustTimer(event.payload / 60);
+        });
     }
 
     // Update tray menu based on current timer state
@@ -547,6 +554,8 @@ export class PomodoroTimer {
         // Update UI to show auto-pause state
         this.updateDisplay();
         this.updateButtons();
+        this.syncTickSound();
+        this.syncTimerState();
         this.updateTrayIcon();
     }
 
@@ -581,6 +590,8 @@ export class PomodoroTimer {
         // Force synchronous UI update first
         this.updateDisplay();
         this.updateButtons();
+        this.syncTickSound();
+        this.syncTimerState();
         this.updateTrayIcon();
 
         // Clear the resume flag after UI update
@@ -688,6 +699,8 @@ export class PomodoroTimer {
             }, 100); // Update more frequently (10 times per second) for smoother display
 
             this.updateButtons();
+            this.syncTickSound();
+            this.syncTimerState();
             this.updateDisplay();
 
             // Clear the resume flag after UI update
@@ -719,6 +732,39 @@ export class PomodoroTimer {
         }
     }
 
+    // Keep the backend's ticking clock in step with the timer: it only ticks
+    // while a focus session is actually running
+    syncTickSound() {
+        const shouldTick = this.isRunning && !this.isPaused && this.currentMode === 'focus';
+        if (shouldTick === this.isTicking) return;
+
+        this.isTicking = shouldTick;
+        invoke(shouldTick ? 'start_tick_sound' : 'stop_tick_sound').catch(error => {
+            console.error('Failed to update tick sound:', error);
+        });
+    }
+
+    // Mirror a running timer to the backend, so a crash or force quit can be
+    // noticed on the next launch. Paused and stopped timers aren't kept.
+    syncTimerState() {
+        let request;
+        if (this.isRunning && !this.isPaused && this.timerStartTime) {
+            request = invoke('save_timer_state', {
+                state: {
+                    mode: this.currentMode,
+                    started_at: new Date(this.timerStartTime).toISOString(),
+                    remaining_secs: Math.max(0, this.timerDuration)
+                }
+            });
+        } else {
+            request = invoke('clear_timer_state');
+        }
+
+        request.catch(error => {
+            console.error('Failed to update saved timer state:', error);
+        });
+    }
+
     // New method for accurate timer updates that works even when app is in background
     updateTimerWithAccuracy() {
         const now = Date.now();
@@ -825,6 +871,8 @@ export class PomodoroTimer {
             this.stopSmartPauseCountdown();
 
             this.updateButtons();
+            this.syncTickSound();
+            this.syncTimerState();
             this.updateDisplay();
             NotificationUtils.showNotificationPing('Timer paused', null, null, 'pause-circle');
 
@@ -865,6 +913,8 @@ export class PomodoroTimer {
         this.timeRemaining = this.durations[this.currentMode];
         this.updateDisplay();
         this.updateButtons();
+        this.syncTickSound();
+        this.syncTimerState();
         NotificationUtils.showNotificationPing('Session deleted', 'warning', null, 'x-circle');
 
         // Update tray menu
@@ -1058,6 +1108,8 @@ export class PomodoroTimer {
             this.timeRemaining = this.durations[this.currentMode];
             this.updateDisplay();
             this.updateButtons();
+            this.syncTickSound();
+            this.syncTimerState();
             if (shouldSaveSession) {
                 this.saveSessionData();
             }
@@ -1149,6 +1201,8 @@ export class PomodoroTimer {
         this.timeRemaining = this.durations[this.currentMode];
         this.updateDisplay();
         this.updateButtons();
+        this.syncTickSound();
+        this.syncTimerState();
         if (shouldSaveSession) {
             this.saveSessionData();
         }
@@ -1199,6 +1253,7 @@ export class PomodoroTimer {
         // Track completion state
         let shouldChangeMode = true;
         let wasFocusSession = false; // Track if this was a focus session
+        const completedMode = this.currentMode;
 
         if (this.currentMode === 'focus') {
             wasFocusSession = true; // Mark as focus session for later use
@@ -1292,9 +1347,12 @@ export class PomodoroTimer {
         this.timeRemaining = this.durations[this.currentMode];
         this.updateDisplay();
         this.updateButtons();
+        this.syncTickSound();
+        this.syncTimerState();
 
         // Only save aggregated session data, individual sessions are handled by saveCompletedFocusSession
         await this.saveSessionData();
+        this.reportSessionCompleted(completedMode);
         this.showNotification();
         if (this.enableSoundNotifications) {
             NotificationUtils.playNotificationSound();
@@ -1417,6 +1475,8 @@ export class PomodoroTimer {
             await this.updateWeeklyStats();
         }
 
+        this.reportSessionCompleted(this.currentMode);
+
         // Show notification
         this.showNotification();
         if (this.enableSoundNotifications) {
@@ -1912,6 +1972,8 @@ export class PomodoroTimer {
         this.updateDisplay();
         await this.updateProgressDots();
         this.updateButtons();
+        this.syncTickSound();
+        this.syncTimerState();
         await this.saveSessionData();
         this.updateTrayIcon();
 
@@ -2205,14 +2267,27 @@ export class PomodoroTimer {
             date: new Date().toDateString()
         };
 
+        // A focus session staged by saveCompletedFocusSession is written in the
+        // same call as the session data and daily stats
+        const manualSession = this.pendingManualSession;
+        this.pendingManualSession = null;
+
         try {
-            await invoke('save_session_data', { session: data });
-            // Also save daily stats for history
-            await invoke('save_daily_stats', { session: data });
+            await invoke('save_session_bundle', {
+                bundle: {
+                    session: data,
+                    manual_session: manualSession,
+                    session_tags: []
+                }
+            });
         } catch (error) {
             console.error('Failed to save session data:', error);
             // Fallback to localStorage
             localStorage.setItem('pomodoro-session', JSON.stringify(data));
+            // The staged session never reached the disk, so drop it from the list again
+            if (manualSession && window.sessionManager) {
+                await window.sessionManager.loadSessionsFromStorage();
+            }
         }
 
         // Update navigation charts if navigation manager is available
@@ -2318,16 +2393,14 @@ export class PomodoroTimer {
             created_at: now.toISOString()
         };
 
-        try {
-            await window.sessionManager.addSession(sessionData);
-            console.log('Timer session saved to SessionManager:', sessionData);
+        // Shown in the session list right away; saveSessionData writes it to disk
+        // together with the day's totals
+        window.sessionManager.recordTimerSession(sessionData);
+        this.pendingManualSession = { ...sessionData, date: now.toDateString() };
+        console.log('Timer session staged for saving:', sessionData);
 
-            // Clear the preserved session start time after successful save
-            this.lastSessionStartTime = null;
-            console.log('Cleared lastSessionStartTime after successful save');
-        } catch (error) {
-            console.error('Failed to save timer session to SessionManager:', error);
-        }
+        // Clear the preserved session start time once the session is recorded
+        this.lastSessionStartTime = null;
     }
 
     async loadSessionData(forceReset = false) {
@@ -2385,6 +2458,19 @@ export class PomodoroTimer {
         }
     }
 
+    // Let the backend run its completion hooks (the automation webhook) for a
+    // session of any mode that just ended
+    reportSessionCompleted(mode) {
+        const seconds = mode === 'focus' ? this.lastCompletedSessionTime : this.durations[mode];
+        invoke('session_completed', {
+            mode,
+            duration: seconds ? Math.round(seconds / 60) : null,
+            completedPomodoros: this.completedPomodoros
+        }).catch(error => {
+            console.error('Failed to report session completion:', error);
+        });
+    }
+
     // Enhanced notification system with better error handling and debugging
     async showNotification() {
         // Only show desktop notifications if the setting is enabled
@@ -2408,7 +2494,7 @@ export class PomodoroTimer {
             // Check if we're in a Tauri context and use Tauri notifications
             if (window.__TAURI__ && window.__TAURI__.notification) {
                 console.log('🔔 Using Tauri notification system');
-                const { isPermissionGranted, requestPermission, sendNotification } = window.__TAURI__.notification;
+                const { isPermissionGranted, requestPermission } = window.__TAURI__.notification;
 
                 // Check if permission is granted
                 let permissionGranted = await isPermissionGranted();
@@ -2428,15 +2514,15 @@ export class PomodoroTimer {
                     }
                 }
 
-                // Send notification if permission is granted
+                // Send notification if permission is granted. The backend
+                // drops it while notifications are muted or in quiet hours.
                 if (permissionGranted) {
                     console.log('🔔 Sending Tauri notification...');
-                    await sendNotification({
+                    const shown = await invoke('notify_session_complete', {
                         title: notificationTitle,
-                        body: notificationBody,
-                        icon: '/assets/tauri.svg'
+                        body: notificationBody
                     });
-                    console.log('✅ Tauri notification sent successfully');
+                    console.log(shown ? '✅ Tauri notification sent successfully' : '🔕 Notification suppressed (muted or quiet hours)');
                 } else {
                     console.warn('❌ Tauri notification permission not available');
                     this.fallbackToWebNotifications(notificationTitle, notificationBody);
@@ -2769,6 +2855,8 @@ export class PomodoroTimer {
         this.isPaused = false;
         this.isAutoPaused = false;
         clearInterval(this.timerInterval);
+        this.syncTickSound();
+        this.syncTimerState();
 
         // Clear smart pause timeout and disable
         if (this.activityTimeout) {
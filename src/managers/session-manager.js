@@ -49,8 +49,10 @@ export class SessionManager {
         }
     }
 
-    // Save sessions to storage (Tauri backend or localStorage fallback)
-    async saveSessionsToStorage() {
+    // Save sessions to storage (Tauri backend or localStorage fallback).
+    // allowLong skips the backend's maximum duration check, for sessions
+    // recorded by the timer itself.
+    async saveSessionsToStorage(allowLong = false) {
         try {
             if (this.isUsingTauri) {
                 // Convert date-keyed object to array for Tauri backend
@@ -64,7 +66,13 @@ export class SessionManager {
                     });
                 });
                 
-                await invoke('save_manual_sessions', { sessions: sessionsArray });
+                try {
+                    await invoke('save_manual_sessions', { sessions: sessionsArray, allowLong });
+                } catch (error) {
+                    // Rejected by validation: drop the local edit so memory matches disk
+                    await this.loadSessionsFromStorage();
+                    throw error;
+                }
                 console.log('Saved', sessionsArray.length, 'manual sessions to Tauri backend');
             } else {
                 // Fallback to localStorage
@@ -73,6 +81,7 @@ export class SessionManager {
             }
         } catch (error) {
             console.error('Error saving sessions to storage:', error);
+            throw error;
         }
     }
 
@@ -311,11 +320,11 @@ export class SessionManager {
 
         } catch (error) {
             console.error('Error saving session:', error);
-            NotificationUtils.showNotificationPing('Failed to save session', 'error');
+            NotificationUtils.showNotificationPing(`Failed to save session: ${error}`, 'error');
         }
     }
 
-    async addSession(sessionData) {
+    async addSession(sessionData, allowLong = false) {
         // Use current date if selectedDate is null (e.g., when called from timer)
         const targetDate = this.selectedDate || new Date();
         const dateString = targetDate.toDateString();
@@ -328,7 +337,24 @@ export class SessionManager {
         this.sessions[dateString].push(sessionData);
         
         // Save to storage (Tauri backend or localStorage)
-        await this.saveSessionsToStorage();
+        await this.saveSessionsToStorage(allowLong);
+
+        // Dispatch session added event for synchronization with other components
+        window.dispatchEvent(new CustomEvent('sessionAdded', { 
+            detail: { sessionData, date: dateString } 
+        }));
+    }
+
+    // Add a session the timer saves itself (with save_session_bundle) to the
+    // list, without writing the whole list back
+    recordTimerSession(sessionData) {
+        const dateString = new Date().toDateString();
+
+        if (!this.sessions[dateString]) {
+            this.sessions[dateString] = [];
+        }
+
+        this.sessions[dateString].push(sessionData);
 
         // Dispatch session added event for synchronization with other components
         window.dispatchEvent(new CustomEvent('sessionAdded', { 
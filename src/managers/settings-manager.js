@@ -8,6 +8,7 @@ export class SettingsManager {
     constructor() {
         this.settings = null;
         this.isRecordingShortcut = false;
+        this.isCapturingShortcut = false; // one-shot capture requested by capture_next_shortcut
         this.currentRecordingField = null;
         this.recordedKeys = [];
         this.autoSaveTimeout = null;
@@ -95,7 +96,17 @@ export class SettingsManager {
             autostart: loadedSettings.autostart !== undefined ? loadedSettings.autostart : defaultSettings.autostart,
             analytics_enabled: loadedSettings.analytics_enabled !== undefined ? loadedSettings.analytics_enabled : defaultSettings.analytics_enabled,
             hide_icon_on_close: loadedSettings.hide_icon_on_close !== undefined ? loadedSettings.hide_icon_on_close : defaultSettings.hide_icon_on_close,
-            status_bar_display: loadedSettings.status_bar_display !== undefined ? loadedSettings.status_bar_display : defaultSettings.status_bar_display
+            status_bar_display: loadedSettings.status_bar_display !== undefined ? loadedSettings.status_bar_display : defaultSettings.status_bar_display,
+            close_behavior: loadedSettings.close_behavior !== undefined ? loadedSettings.close_behavior : defaultSettings.close_behavior,
+            tray_title_format: loadedSettings.tray_title_format !== undefined ? loadedSettings.tray_title_format : defaultSettings.tray_title_format,
+            preferred_monitor: loadedSettings.preferred_monitor !== undefined ? loadedSettings.preferred_monitor : defaultSettings.preferred_monitor,
+            break_always_on_top: loadedSettings.break_always_on_top !== undefined ? loadedSettings.break_always_on_top : defaultSettings.break_always_on_top,
+            mini_mode_frameless: loadedSettings.mini_mode_frameless !== undefined ? loadedSettings.mini_mode_frameless : defaultSettings.mini_mode_frameless,
+            mini_mode_always_on_top: loadedSettings.mini_mode_always_on_top !== undefined ? loadedSettings.mini_mode_always_on_top : defaultSettings.mini_mode_always_on_top,
+            normal_window_size: loadedSettings.normal_window_size !== undefined ? loadedSettings.normal_window_size : defaultSettings.normal_window_size,
+            mode_icons: loadedSettings.mode_icons !== undefined ? loadedSettings.mode_icons : defaultSettings.mode_icons,
+            theme: loadedSettings.theme !== undefined ? loadedSettings.theme : defaultSettings.theme,
+            start_hidden_from_dock: loadedSettings.start_hidden_from_dock !== undefined ? loadedSettings.start_hidden_from_dock : defaultSettings.start_hidden_from_dock
         };
     }
 
@@ -104,7 +115,8 @@ export class SettingsManager {
             shortcuts: {
                 start_stop: "CommandOrControl+Alt+Space",
                 reset: "CommandOrControl+Alt+R", // Delete Session (focus) / Undo (break)
-                skip: "CommandOrControl+Alt+S"   // Save Session
+                skip: "CommandOrControl+Alt+S",  // Save Session
+                extend: "CommandOrControl+Alt+E" // Add 5 minutes
             },
             timer: {
                 focus_duration: 25,
@@ -112,6 +124,8 @@ export class SettingsManager {
                 long_break_duration: 20,
                 total_sessions: 10,
                 weekly_goal_minutes: 125,
+                daily_goal_minutes: 0, // 0 = weekly goal / 5
+                week_start_day: "monday", // monday or sunday
                 max_session_time: 120 // 2 hours in minutes
             },
             notifications: {
@@ -127,12 +141,29 @@ export class SettingsManager {
                 timer_theme: "espresso" // Timer color theme
             },
             advanced: {
-                debug_mode: false // Debug mode with 3-second timers
+                debug_mode: false, // Debug mode with 3-second timers
+                max_manual_session_minutes: 480, // Longer manual sessions are rejected
+                durable_writes: true, // fsync settings and history on save
+                auto_export_enabled: false,
+                auto_export_path: "", // Folder the scheduled export is written to
+                auto_export_interval_hours: 24,
+                auto_export_timestamped: false, // Keep every export instead of overwriting
+                completion_webhook_url: null // POSTed to when a session completes
             },
             autostart: false, // default to disabled
             analytics_enabled: true, // Analytics enabled by default
             hide_icon_on_close: false, // Hide icon on close disabled by default
-            status_bar_display: 'default' // Status bar display mode: 'default' or 'icon-only'
+            status_bar_display: 'default', // Status bar display mode: 'default' or 'icon-only'
+            close_behavior: 'minimize', // Window close behavior: 'minimize' (to tray) or 'quit'
+            tray_title_format: '{icon} {time}', // Placeholders: {icon} {time} {mode} {session} {total}
+            preferred_monitor: null, // Monitor name to open the window on, null = keep current
+            break_always_on_top: false, // Keep the window in front during breaks
+            mini_mode_frameless: false, // Hide the title bar in mini mode
+            mini_mode_always_on_top: false,
+            normal_window_size: null, // Restored when leaving mini mode
+            mode_icons: {}, // Custom tray icons per mode, set via set_mode_icons
+            theme: "system", // system, light, dark
+            start_hidden_from_dock: false // Launch without a dock/taskbar icon
         };
     }
 
@@ -353,6 +384,11 @@ export class SettingsManager {
                         window.pomodoroTimer.skipSession();
                     }
                     break;
+                case 'extend':
+                    if (window.pomodoroTimer) {
+                        window.pomodoroTimer.adjustTimer(5);
+                    }
+                    break;
             }
         });
 
@@ -366,6 +402,25 @@ export class SettingsManager {
                 window.pomodoroTimer.updateKeyboardShortcuts(this.settings.shortcuts);
             }
         });
+
+        // capture_next_shortcut can't hear arbitrary keys through the global
+        // shortcut plugin, so forward the next combination pressed here
+        window.__TAURI__.event.listen('shortcut-capture-started', () => {
+            this.isCapturingShortcut = true;
+        });
+        window.__TAURI__.event.listen('shortcut-capture-ended', () => {
+            this.isCapturingShortcut = false;
+        });
+        window.addEventListener('keydown', (e) => {
+            if (!this.isCapturingShortcut) return;
+
+            const keys = this.getShortcutKeys(e);
+            if (!keys) return;
+
+            e.preventDefault();
+            e.stopPropagation();
+            window.__TAURI__.event.emit('shortcut-capture-key', this.formatShortcut(keys));
+        }, true);
     }
 
     startRecordingShortcut(input) {
@@ -406,17 +461,10 @@ export class SettingsManager {
         e.preventDefault();
         e.stopPropagation();
 
-        const key = e.key;
-        const modifiers = [];
-
-        if (e.metaKey || e.ctrlKey) modifiers.push('CommandOrControl');
-        if (e.altKey) modifiers.push('Alt');
-        if (e.shiftKey) modifiers.push('Shift');
-
-        // Don't record modifier keys alone
-        if (['Meta', 'Control', 'Alt', 'Shift'].includes(key)) return;
+        const keys = this.getShortcutKeys(e);
+        if (!keys) return;
 
-        this.recordedKeys = [...modifiers, key];
+        this.recordedKeys = keys;
 
         if (this.currentRecordingField) {
             this.currentRecordingField.value = this.formatShortcut(this.recordedKeys);
@@ -430,6 +478,21 @@ export class SettingsManager {
         }, 500);
     }
 
+    // Modifiers plus the pressed key, or null for a lone modifier
+    getShortcutKeys(e) {
+        const key = e.key;
+        const modifiers = [];
+
+        if (e.metaKey || e.ctrlKey) modifiers.push('CommandOrControl');
+        if (e.altKey) modifiers.push('Alt');
+        if (e.shiftKey) modifiers.push('Shift');
+
+        // Don't record modifier keys alone
+        if (['Meta', 'Control', 'Alt', 'Shift'].includes(key)) return null;
+
+        return [...modifiers, key];
+    }
+
     formatShortcut(keys) {
         return keys.join('+');
     }
@@ -300,7 +300,7 @@ export class NotificationUtils {
         try {
             // Check if we're in a Tauri context
             if (window.__TAURI__ && window.__TAURI__.notification) {
-                const { isPermissionGranted, requestPermission, sendNotification } = window.__TAURI__.notification;
+                const { isPermissionGranted, requestPermission } = window.__TAURI__.notification;
 
                 // Check if permission is granted
                 let permissionGranted = await isPermissionGranted();
@@ -311,12 +311,12 @@ export class NotificationUtils {
                     permissionGranted = permission === 'granted';
                 }
 
-                // Send notification if permission is granted
+                // Send through the backend so mute and quiet hours apply
                 if (permissionGranted) {
-                    await sendNotification({
+                    const { invoke } = window.__TAURI__.core;
+                    await invoke('notify_session_complete', {
                         title: title,
-                        body: message,
-                        icon: icon
+                        body: message
                     });
                 } else {
                     console.warn('Notification permission denied');